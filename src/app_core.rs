@@ -0,0 +1,306 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    log,
+    logger::format_timestamp,
+    sanitization::SanitizationConfig,
+    state::{load_state, save_state, Pause, State},
+    watcher::{ClipboardBackend, ClipboardWatcher},
+    Config,
+};
+
+/// Platform-independent app state shared between the clipboard watcher, tray menu and IPC server
+#[derive(Debug)]
+pub struct AppCore {
+    pub config: Config,
+    state: State,
+    sanitization: SanitizationConfig,
+    pub watcher: ClipboardWatcher,
+}
+
+pub type SharedAppCore = Arc<Mutex<AppCore>>;
+
+/// Durations of the timed pauses offered in the tray menus
+pub const PAUSE_MENU_MINUTES: [u64; 2] = [15, 60];
+
+pub fn pause_menu_label(minutes: u64) -> String {
+    match minutes {
+        60 => "Pause for 1 Hour".into(),
+        m if m % 60 == 0 => format!("Pause for {} Hours", m / 60),
+        m => format!("Pause for {m} Minutes"),
+    }
+}
+
+/// Entry in the profiles section of the tray menu, the first entry is always the base config (no profile)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileMenuEntry {
+    pub label: String,
+    pub profile: Option<String>,
+    pub active: bool,
+}
+
+impl AppCore {
+    pub fn new(config: Config) -> Self {
+        Self::with_state(config, load_state())
+    }
+
+    fn with_state(config: Config, mut state: State) -> Self {
+        if let Some(profile) = &state.active_profile {
+            if !config.profiles.contains_key(profile) {
+                log!(
+                    Warning,
+                    "Previously active profile {profile:?} no longer exists, using no profile"
+                );
+                state.active_profile = None;
+            }
+        }
+
+        if !config.persist_pause {
+            state.pause = None;
+        }
+
+        let mut core = Self {
+            watcher: ClipboardWatcher::new(&config),
+            config,
+            state,
+            sanitization: SanitizationConfig::default(),
+        };
+        core.update_sanitization_config();
+
+        core
+    }
+
+    pub fn shared(self) -> SharedAppCore {
+        Arc::new(Mutex::new(self))
+    }
+
+    fn update_sanitization_config(&mut self) {
+        self.sanitization = match &self.state.active_profile {
+            Some(profile) => self.config.profiles[profile].apply_to(&self.config.sanitization),
+            None => self.config.sanitization.clone(),
+        };
+    }
+
+    /// Sanitization settings of the active profile (if any) applied to the base config
+    pub fn sanitization_config(&self) -> &SanitizationConfig {
+        &self.sanitization
+    }
+
+    fn persist_state(&self) {
+        let mut state = self.state.clone();
+
+        if !self.config.persist_pause {
+            state.pause = None;
+        }
+
+        save_state(&state);
+    }
+
+    /// Sanitizes the clipboard with the active sanitization settings, to be called whenever the clipboard changed
+    pub fn handle_clipboard_changed(&mut self, clipboard: &mut dyn ClipboardBackend) {
+        if self.is_paused() {
+            return;
+        }
+
+        self.watcher
+            .handle_clipboard_changed(clipboard, &self.sanitization);
+    }
+
+    /// Resumes sanitizing once a timed pause expired & performs the watcher's delayed work, to be called periodically
+    /// from the thread the clipboard is accessible on
+    pub fn tick(&mut self, clipboard: &mut dyn ClipboardBackend) {
+        self.watcher.tick(clipboard);
+
+        if self
+            .state
+            .pause
+            .is_some_and(|pause| pause.is_expired(SystemTime::now()))
+        {
+            log!(Info, "Pause expired");
+            self.resume();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state
+            .pause
+            .is_some_and(|pause| !pause.is_expired(SystemTime::now()))
+    }
+
+    /// Pauses automatic sanitizing for the given duration, or until resumed when `None`
+    pub fn pause(&mut self, duration: Option<Duration>) -> Result<(), String> {
+        self.state.pause = Some(match duration {
+            Some(duration) => Pause::for_duration(SystemTime::now(), duration)
+                .ok_or("Can't pause for that long, pause until resumed instead")?,
+            None => Pause::until_resumed(),
+        });
+
+        self.persist_state();
+
+        log!(Info, "{}", self.describe_pause());
+
+        Ok(())
+    }
+
+    pub fn resume(&mut self) {
+        self.state.pause = None;
+
+        self.persist_state();
+
+        log!(Info, "{}", self.describe_pause());
+    }
+
+    pub fn describe_pause(&self) -> String {
+        if !self.is_paused() {
+            return "Sanitizing is active".into();
+        }
+
+        match self.state.pause.and_then(|pause| pause.until()) {
+            Some(until) => format!("Sanitizing is paused until {}", format_timestamp(until)),
+            None => "Sanitizing is paused until resumed".into(),
+        }
+    }
+
+    pub fn active_profile(&self) -> Option<&str> {
+        self.state.active_profile.as_deref()
+    }
+
+    /// Switches to the given profile (or back to the base config when `None`) and persists the choice
+    pub fn set_active_profile(&mut self, profile: Option<&str>) -> Result<(), String> {
+        if let Some(profile) = profile {
+            if !self.config.profiles.contains_key(profile) {
+                return Err(format!(
+                    "No profile named {profile:?}, expected one of: {}",
+                    self.profile_names().join(", ")
+                ));
+            }
+        }
+
+        self.state.active_profile = profile.map(|p| p.to_string());
+        self.update_sanitization_config();
+
+        self.persist_state();
+
+        log!(Info, "Switched to profile {profile:?}");
+
+        Ok(())
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.config.profiles.keys().cloned().collect()
+    }
+
+    pub fn profile_menu_entries(&self) -> Vec<ProfileMenuEntry> {
+        [(None, "No Profile".to_string())]
+            .into_iter()
+            .chain(
+                self.profile_names()
+                    .into_iter()
+                    .map(|p| (Some(p.clone()), p)),
+            )
+            .map(|(profile, label)| ProfileMenuEntry {
+                active: profile.as_deref() == self.active_profile(),
+                label,
+                profile,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sanitization::SanitizationProfile;
+
+    fn config_with_profiles() -> Config {
+        let mut config = Config::default();
+        config
+            .profiles
+            .insert("work".into(), SanitizationProfile::default());
+        config
+    }
+
+    #[test]
+    fn test_overlong_pause_is_rejected() {
+        let mut core = AppCore::with_state(Config::default(), State::default());
+
+        assert!(core.pause(Some(Duration::MAX)).is_err());
+        assert!(!core.is_paused());
+    }
+
+    #[test]
+    fn test_unknown_persisted_profile_is_dropped() {
+        let core = AppCore::with_state(
+            config_with_profiles(),
+            State {
+                active_profile: Some("gone".into()),
+                pause: None,
+            },
+        );
+
+        assert_eq!(core.active_profile(), None);
+    }
+
+    #[test]
+    fn test_profile_menu_entries() {
+        let core = AppCore::with_state(
+            config_with_profiles(),
+            State {
+                active_profile: Some("work".into()),
+                pause: None,
+            },
+        );
+
+        assert_eq!(
+            core.profile_menu_entries(),
+            vec![
+                ProfileMenuEntry {
+                    label: "No Profile".into(),
+                    profile: None,
+                    active: false,
+                },
+                ProfileMenuEntry {
+                    label: "work".into(),
+                    profile: Some("work".into()),
+                    active: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_is_only_restored_when_persisted() {
+        let state = State {
+            active_profile: None,
+            pause: Some(Pause::until_resumed()),
+        };
+
+        let core = AppCore::with_state(Config::default(), state.clone());
+        assert!(!core.is_paused());
+
+        let config = Config {
+            persist_pause: true,
+            ..Config::default()
+        };
+
+        let core = AppCore::with_state(config, state);
+        assert!(core.is_paused());
+        assert_eq!(core.describe_pause(), "Sanitizing is paused until resumed");
+    }
+
+    #[test]
+    fn test_pause_menu_labels() {
+        let labels: Vec<String> = [5, 60, 120].into_iter().map(pause_menu_label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Pause for 5 Minutes",
+                "Pause for 1 Hour",
+                "Pause for 2 Hours"
+            ]
+        );
+    }
+}
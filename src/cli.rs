@@ -0,0 +1,105 @@
+use std::io::{self, Read};
+
+use crate::{
+    app_core::AppCore,
+    config::{config_json_schema, load_config},
+    ipc,
+    sanitization::{sanitize_html, sanitize_rtf, sanitize_with_config, SanitizationConfig},
+};
+
+const USAGE: &str = "Usage: clipboard_cleanse [COMMAND]
+
+Starts the app when no command is given.
+
+Commands:
+  config show    Print the effective config and where each value came from
+  config schema  Print the JSON Schema of the config file
+  profile list   List the configured profiles, marking the active one
+  profile use    Switch to the given profile
+  profile clear  Switch back to the base config (no profile)
+  pause          Pause sanitizing until resumed
+  pause <minutes>
+                 Pause sanitizing for the given number of minutes
+  resume         Resume sanitizing
+  history list   List the recently sanitized copies, most recent first
+  history restore <number>
+                 Put the original text of a history entry back onto the clipboard
+  sanitize [--html | --rtf]
+                 Sanitize text (or an HTML fragment or RTF document) read from stdin with the active profile
+  help           Print this message";
+
+/// Runs a command against the running app, or directly against the persisted state if the app isn't running and
+/// the command doesn't need it to be
+fn run_app_command(args: &[&str], requires_running_app: bool) -> i32 {
+    let result = match ipc::send_command(args) {
+        Ok(result) => result,
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotConnected {
+                eprintln!("Failed to communicate with the running app: {err}");
+                return 1;
+            }
+
+            if requires_running_app {
+                eprintln!("The app isn't running");
+                return 1;
+            }
+
+            ipc::execute_command(&mut AppCore::new(load_config()), args)
+        }
+    };
+
+    match result {
+        Ok(output) => {
+            println!("{output}");
+            0
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+fn run_sanitize(sanitize: fn(&str, &SanitizationConfig) -> String) -> i32 {
+    let mut input = String::new();
+
+    if let Err(err) = io::stdin().read_to_string(&mut input) {
+        eprintln!("Failed to read stdin: {err}");
+        return 1;
+    }
+
+    let core = AppCore::new(load_config());
+    let config = core.sanitization_config();
+
+    print!("{}", sanitize(&input, config));
+    0
+}
+
+/// Runs the command specified by the command-line arguments, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    match args.as_slice() {
+        ["config", "show"] => {
+            println!("{}", load_config().describe_effective());
+            0
+        }
+        ["config", "schema"] => {
+            println!("{}", config_json_schema());
+            0
+        }
+        ["profile", ..] => run_app_command(&args, false),
+        ["sanitize"] => run_sanitize(sanitize_with_config),
+        ["sanitize", "--html"] => run_sanitize(|html, config| sanitize_html(html, config).0),
+        ["sanitize", "--rtf"] => run_sanitize(|rtf, config| sanitize_rtf(rtf, config).0),
+        ["pause", ..] | ["resume"] | ["history", ..] => run_app_command(&args, true),
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{USAGE}");
+            0
+        }
+        _ => {
+            eprintln!("Unknown command: {:?}\n\n{USAGE}", args.join(" "));
+            2
+        }
+    }
+}
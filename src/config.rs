@@ -1,16 +1,40 @@
 use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
     fs::{create_dir_all, File},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::{
+    exclusions::ExclusionConfig,
+    log,
+    logger::{FileLogOptions, LogFormat, LogRedaction},
+    sanitization::{SanitizationConfig, SanitizationProfile},
+    secrets::SecretsConfig,
+};
 
 #[cfg(target_os = "windows")]
-use crate::windows::{get_home_directory, Config as WindowsConfig};
+use crate::windows::{get_home_directory, get_system_config_directory, Config as WindowsConfig};
 
 #[cfg(target_os = "macos")]
-use crate::macos::{get_home_directory, Config as MacOSConfig};
+use crate::macos::{get_home_directory, get_system_config_directory, Config as MacOSConfig};
+
+/// Prefix of environment variables which override config values, e.g. `CLIPBOARD_CLEANSE_LOG_LEVEL=DEBUG`.
+/// Nested keys are separated by a double underscore, e.g. `CLIPBOARD_CLEANSE_MACOS__POLL_INTERVAL_MS=250`.
+const ENV_OVERRIDE_PREFIX: &str = "CLIPBOARD_CLEANSE_";
+
+/// Name of the JSON Schema file written next to the user's config, referenced by the config's header
+const SCHEMA_FILE_NAME: &str = "config.schema.json";
+
+/// Key in the system config listing the (dotted) keys users are not allowed to override
+const LOCKED_KEYS_KEY: &str = "locked";
 
 fn config_default_log_level() -> String {
     "INFO".into()
@@ -20,17 +44,145 @@ fn config_default_log_file() -> Option<String> {
     None
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+fn config_default_log_append() -> bool {
+    true
+}
+
+fn config_default_log_max_size_bytes() -> Option<u64> {
+    Some(10 * 1024 * 1024)
+}
+
+fn config_default_log_retention() -> usize {
+    5
+}
+
+fn config_default_history_size() -> usize {
+    20
+}
+
+fn config_default_bypass_window_ms() -> u64 {
+    2000
+}
+
+fn config_default_max_payload_bytes() -> usize {
+    1024 * 1024
+}
+
+fn config_default_sanitize_timeout_ms() -> u64 {
+    500
+}
+
+/// Where the effective value of a config key came from, ordered by increasing precedence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    System,
+    User,
+    Environment,
+    /// Set by the system config and listed in its `locked` keys
+    SystemLocked,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Default => "default",
+                Self::System => "system",
+                Self::User => "user",
+                Self::Environment => "environment",
+                Self::SystemLocked => "system (locked)",
+            }
+        )
+    }
+}
+
+/// Clipboard Cleanse configuration, see https://github.com/Iapetus-11/clipboard-cleanse#configuration
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Config {
     #[serde(skip_serializing, default)]
+    #[schemars(skip)]
     pub config_path: String,
 
+    /// Source of each effective (dotted) config key, see [`ConfigSource`]
+    #[serde(skip, default)]
+    pub value_sources: BTreeMap<String, ConfigSource>,
+
+    /// Minimum level of logged messages, one of TRACE, DEBUG, INFO, WARNING, or ERROR. Also accepts RUST_LOG-style
+    /// directives to set levels per module, e.g. "INFO,clipboard_cleanse::sanitization=DEBUG,url=OFF"
     #[serde(default = "config_default_log_level")]
     pub log_level: String,
 
+    /// Path of a file to write logs to in addition to stdout
     #[serde(default = "config_default_log_file")]
     pub log_file: Option<String>,
 
+    /// How much of copied URLs may be logged: "strict" (hosts & parameter names only), "hashed" (values replaced by a
+    /// hash) or "off" (everything, which may include passwords or personal data)
+    #[serde(default)]
+    pub log_redaction: LogRedaction,
+
+    /// Whether to keep the existing contents of the log file on startup instead of truncating it
+    #[serde(default = "config_default_log_append")]
+    pub log_append: bool,
+
+    /// Format of the lines written to the log file, either "text" or "json" (JSON lines)
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    /// Rotate the log file once it would grow beyond this many bytes
+    #[serde(default = "config_default_log_max_size_bytes")]
+    pub log_max_size_bytes: Option<u64>,
+
+    /// Rotate the log file once it has been written to for longer than this many hours
+    #[serde(default)]
+    pub log_max_age_hours: Option<u64>,
+
+    /// How many rotated log files (`<log_file>.1`, `<log_file>.2`, ...) to keep
+    #[serde(default = "config_default_log_retention")]
+    pub log_retention: usize,
+
+    /// How many sanitized copies to remember (in memory only) so their original text can be restored with
+    /// `clipboard_cleanse history restore <number>`, 0 disables the history
+    #[serde(default = "config_default_history_size")]
+    pub history_size: usize,
+
+    /// Copying the same text again within this many milliseconds after it was sanitized keeps it as-is, 0 disables
+    /// this
+    #[serde(default = "config_default_bypass_window_ms")]
+    pub bypass_window_ms: u64,
+
+    /// Whether pausing sanitizing (from the tray menu or with `clipboard_cleanse pause`) is remembered across
+    /// restarts
+    #[serde(default)]
+    pub persist_pause: bool,
+
+    /// Copied text larger than this many bytes is left untouched, 0 means no limit
+    #[serde(default = "config_default_max_payload_bytes")]
+    pub max_payload_bytes: usize,
+
+    /// Copied text is left untouched if sanitizing it takes longer than this many milliseconds, 0 means no limit
+    #[serde(default = "config_default_sanitize_timeout_ms")]
+    pub sanitize_timeout_ms: u64,
+
+    /// Copied text which is left untouched entirely, e.g. code or test data
+    #[serde(default)]
+    pub exclusions: ExclusionConfig,
+
+    /// Clearing copied secrets (API keys, tokens, private keys, ...) from the clipboard after a while
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    #[serde(flatten)]
+    pub sanitization: SanitizationConfig,
+
+    /// Named profiles which override the sanitization settings above while active. Switch between them from the
+    /// tray menu or with `clipboard_cleanse profile use <name>`, the last active profile is remembered.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, SanitizationProfile>,
+
     #[cfg(target_os = "macos")]
     #[serde(default = "MacOSConfig::default")]
     pub macos: MacOSConfig,
@@ -44,8 +196,24 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             config_path: "".into(),
+            value_sources: BTreeMap::new(),
             log_level: "INFO".into(),
             log_file: None,
+            log_redaction: LogRedaction::default(),
+            log_append: config_default_log_append(),
+            log_format: LogFormat::default(),
+            log_max_size_bytes: config_default_log_max_size_bytes(),
+            log_max_age_hours: None,
+            log_retention: config_default_log_retention(),
+            history_size: config_default_history_size(),
+            bypass_window_ms: config_default_bypass_window_ms(),
+            persist_pause: false,
+            max_payload_bytes: config_default_max_payload_bytes(),
+            sanitize_timeout_ms: config_default_sanitize_timeout_ms(),
+            exclusions: ExclusionConfig::default(),
+            secrets: SecretsConfig::default(),
+            sanitization: SanitizationConfig::default(),
+            profiles: BTreeMap::new(),
 
             #[cfg(target_os = "macos")]
             macos: MacOSConfig::default(),
@@ -56,64 +224,629 @@ impl Default for Config {
     }
 }
 
-fn get_config_file_path() -> PathBuf {
+impl Config {
+    /// Describes the config for logging, only listing which keys were set (but not their values) unless redaction
+    /// is off
+    pub fn describe_for_log(&self, redaction: LogRedaction) -> String {
+        match redaction {
+            LogRedaction::Off => format!("{self:#?}"),
+            _ => {
+                let set_keys: Vec<String> = self
+                    .value_sources
+                    .iter()
+                    .filter(|(_, source)| **source != ConfigSource::Default)
+                    .map(|(key, source)| format!("{key} ({source})"))
+                    .collect();
+
+                format!(
+                    "{:?} with {} set",
+                    self.config_path,
+                    match set_keys.is_empty() {
+                        true => "nothing".into(),
+                        false => set_keys.join(", "),
+                    }
+                )
+            }
+        }
+    }
+
+    pub fn file_log_options(&self) -> FileLogOptions {
+        FileLogOptions {
+            append: self.log_append,
+            format: self.log_format,
+            max_size_bytes: self.log_max_size_bytes,
+            max_age: self
+                .log_max_age_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60)),
+            retention: self.log_retention,
+        }
+    }
+
+    /// Renders the effective config as TOML-ish `key = value` lines annotated with the source of each value
+    pub fn describe_effective(&self) -> String {
+        let table = Value::try_from(self)
+            .ok()
+            .and_then(|v| v.as_table().cloned())
+            .unwrap_or_default();
+
+        let mut lines = Vec::new();
+
+        for (key, value) in flatten_table(&table) {
+            let source = self
+                .value_sources
+                .get(&key)
+                .copied()
+                .unwrap_or(ConfigSource::Default);
+
+            lines.push(format!("{key} = {value} # {source}"));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Directory containing the user's config, as well as other files the app persists
+pub fn get_config_directory() -> PathBuf {
     let mut home_dir = get_home_directory();
 
-    home_dir.extend([".config", "clipboard_cleanse", "config.toml"]);
+    home_dir.extend([".config", "clipboard_cleanse"]);
 
     home_dir
 }
 
-fn write_config(config_path: &PathBuf, config: &Config) {
-    let serialized_config: String =
-        "# Please restart Clipboard Cleanse to apply any configuration changes\n\n".to_string()
-            + &toml::to_string_pretty(&config)
-                .unwrap_or_else(|_| panic!("Serialization of config {:#?} to succeed", config));
+fn get_config_file_path() -> PathBuf {
+    get_config_directory().join("config.toml")
+}
 
-    let mut file = File::create(config_path)
-        .unwrap_or_else(|_| panic!("Config file creation at {:#?} to succeed", config_path));
+fn get_system_config_file_path() -> PathBuf {
+    get_system_config_directory().join("config.toml")
+}
+
+/// Generates a JSON Schema describing the config file for this platform
+pub fn config_json_schema() -> String {
+    serde_json::to_string_pretty(&schema_for!(Config))
+        .expect("Serialization of config schema to succeed")
+}
+
+fn write_config_schema(config_path: &Path) {
+    let schema_path = config_path.with_file_name(SCHEMA_FILE_NAME);
+
+    let result = File::create(&schema_path)
+        .and_then(|mut file| file.write_all(config_json_schema().as_bytes()));
+
+    if let Err(err) = result {
+        log!(
+            Warning,
+            "Failed to write config schema to {schema_path:#?}: {err}"
+        );
+    }
+}
+
+/// Contents of a new user config, listing the defaults commented out so that they don't shadow the system config
+fn default_user_config(defaults: &Table) -> String {
+    let commented_defaults: Vec<String> = toml::to_string_pretty(defaults)
+        .unwrap_or_else(|_| panic!("Serialization of config {:#?} to succeed", defaults))
+        .lines()
+        .map(|line| match line {
+            "" => String::new(),
+            _ => format!("# {line}"),
+        })
+        .collect();
+
+    // The #:schema directive lets editors with TOML schema support (e.g. Taplo) validate & autocomplete the file
+    format!(
+        "#:schema ./{SCHEMA_FILE_NAME}\n# Please restart Clipboard Cleanse to apply any configuration changes\n# The defaults are listed below, uncomment a value to change it\n\n{}\n",
+        commented_defaults.join("\n")
+    )
+}
 
-    file.write_all(serialized_config.as_bytes())
-        .expect("Writing default config to succeed");
+fn default_config_table() -> Table {
+    Value::try_from(Config::default())
+        .expect("Serialization of default config to succeed")
+        .as_table()
+        .cloned()
+        .unwrap()
 }
 
-/// Ensures the config exists as the specified path, returning true if it had to be created from the defaults.
-fn ensure_config_exists(config_path: &PathBuf) -> bool {
+/// Ensures the config exists as the specified path, creating it from the defaults if it doesn't
+fn ensure_config_exists(config_path: &PathBuf, defaults: &Table) {
     if !config_path.try_exists().unwrap() {
         if !config_path.parent().unwrap().exists() {
             create_dir_all(config_path.parent().unwrap()).expect("Creating config dir to succeed");
         }
 
-        write_config(config_path, &Config::default());
+        let mut file = File::create(config_path)
+            .unwrap_or_else(|_| panic!("Config file creation at {:#?} to succeed", config_path));
 
-        true
-    } else {
-        false
+        file.write_all(default_user_config(defaults).as_bytes())
+            .expect("Writing default config to succeed");
     }
 }
 
-pub fn load_and_ensure_config() -> Config {
+fn read_config_table(config_path: &Path) -> Table {
+    let mut config_file_data = Vec::<u8>::new();
+    let mut config_file = File::open(config_path)
+        .unwrap_or_else(|_| panic!("Opening config file at {:#?} to succeed", config_path));
+    config_file
+        .read_to_end(&mut config_file_data)
+        .expect("Reading config file to succeed");
+
+    toml::from_str(&String::from_utf8(config_file_data).expect("Valid utf8"))
+        .unwrap_or_else(|err| panic!("Config file at {:#?} to be valid: {err}", config_path))
+}
+
+/// Flattens nested tables into dotted keys, arrays and other values are left as leaves
+fn flatten_table(table: &Table) -> Vec<(String, Value)> {
+    fn flatten_into(prefix: &str, table: &Table, out: &mut Vec<(String, Value)>) {
+        for (key, value) in table {
+            let key = match prefix {
+                "" => key.clone(),
+                _ => format!("{prefix}.{key}"),
+            };
+
+            match value {
+                Value::Table(inner) => flatten_into(&key, inner, out),
+                _ => out.push((key, value.clone())),
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    flatten_into("", table, &mut out);
+    out
+}
+
+fn is_locked(key: &str, locked_keys: &[String]) -> bool {
+    locked_keys
+        .iter()
+        .any(|lk| key == lk || key.starts_with(&format!("{lk}.")))
+}
+
+fn get_value<'a>(table: &'a Table, key_parts: &[String]) -> Option<&'a Value> {
+    match key_parts {
+        [key] => table.get(key),
+        [head, rest @ ..] => get_value(table.get(head)?.as_table()?, rest),
+        [] => None,
+    }
+}
+
+fn insert_value(table: &mut Table, key_parts: &[String], value: Value) {
+    let Some((key, parents)) = key_parts.split_last() else {
+        return;
+    };
+
+    let mut current = table;
+    for part in parents {
+        let entry = current
+            .entry(part.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+
+        current = entry.as_table_mut().unwrap();
+    }
+
+    current.insert(key.clone(), value);
+}
+
+/// Merges `layer` into `base`, recursing into tables and replacing everything else, except for arrays set by a
+/// previous layer other than the defaults, which are appended to (e.g. to add to an organisation's `allowlist`). Keys
+/// matching one of `locked_keys` are left alone. Sources of the merged leaf values are recorded in `sources`.
+fn merge_layer(
+    base: &mut Table,
+    layer: &Table,
+    source: ConfigSource,
+    locked_keys: &[String],
+    sources: &mut BTreeMap<String, ConfigSource>,
+) {
+    fn merge_into(
+        prefix: &str,
+        base: &mut Table,
+        layer: &Table,
+        source: ConfigSource,
+        locked_keys: &[String],
+        sources: &mut BTreeMap<String, ConfigSource>,
+    ) {
+        for (key, value) in layer {
+            let dotted_key = match prefix {
+                "" => key.clone(),
+                _ => format!("{prefix}.{key}"),
+            };
+
+            if let (Value::Table(layer_inner), Some(Value::Table(base_inner))) =
+                (value, base.get_mut(key))
+            {
+                merge_into(
+                    &dotted_key,
+                    base_inner,
+                    layer_inner,
+                    source,
+                    locked_keys,
+                    sources,
+                );
+                continue;
+            }
+
+            if is_locked(&dotted_key, locked_keys) {
+                log!(
+                    Warning,
+                    "Ignoring {source} config value for {dotted_key:?} as it is locked by the system config"
+                );
+                continue;
+            }
+
+            let value = match (value, base.get(key), sources.get(&dotted_key)) {
+                (Value::Array(items), Some(Value::Array(base_items)), Some(base_source))
+                    if *base_source != ConfigSource::Default =>
+                {
+                    Value::Array(base_items.iter().chain(items).cloned().collect())
+                }
+                _ => value.clone(),
+            };
+
+            base.insert(key.clone(), value.clone());
+
+            sources.retain(|k, _| k != &dotted_key && !k.starts_with(&format!("{dotted_key}.")));
+            match &value {
+                Value::Table(inner) => {
+                    for (inner_key, _) in flatten_table(inner) {
+                        sources.insert(format!("{dotted_key}.{inner_key}"), source);
+                    }
+                }
+                _ => {
+                    sources.insert(dotted_key, source);
+                }
+            }
+        }
+    }
+
+    merge_into("", base, layer, source, locked_keys, sources);
+}
+
+/// Parses a raw environment variable value as a TOML value, falling back to a plain string
+fn parse_env_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Builds a config layer out of `CLIPBOARD_CLEANSE_*` variables. Values overriding a string are taken as-is, and
+/// overrides which would make the config invalid when applied to `base` are logged & skipped.
+fn env_overrides_table(vars: impl Iterator<Item = (String, String)>, base: &Table) -> Table {
+    let mut table = Table::new();
+
+    for (name, raw_value) in vars {
+        let Some(key) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+
+        let key_parts: Vec<String> = key
+            .split("__")
+            .map(|p| p.to_ascii_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        if key_parts.is_empty() {
+            continue;
+        }
+
+        let parsed = match get_value(base, &key_parts) {
+            Some(Value::String(_)) => Value::String(raw_value.clone()),
+            _ => parse_env_value(&raw_value),
+        };
+
+        // Values of keys which aren't set (e.g. an unset `log_file`) may be strings which look like other types
+        let valid_value = [parsed, Value::String(raw_value)]
+            .into_iter()
+            .find(|value| {
+                let mut candidate = base.clone();
+                insert_value(&mut candidate, &key_parts, value.clone());
+                Value::Table(candidate).try_into::<Config>().is_ok()
+            });
+
+        match valid_value {
+            Some(value) => insert_value(&mut table, &key_parts, value),
+            None => log!(
+                Error,
+                "Ignoring environment variable {name} as its value is invalid for the config"
+            ),
+        }
+    }
+
+    table
+}
+
+/// Takes the locked keys out of a system config layer
+fn take_locked_keys(system: &mut Table) -> Vec<String> {
+    match system.remove(LOCKED_KEYS_KEY) {
+        Some(Value::Array(keys)) => keys
+            .into_iter()
+            .filter_map(|k| k.as_str().map(|s| s.to_string()))
+            .collect(),
+        Some(other) => {
+            log!(
+                Error,
+                "Expected {LOCKED_KEYS_KEY:?} in system config to be an array of keys, got {other:?}"
+            );
+            Vec::new()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Merges the defaults, system, user and environment layers (in that order of precedence), where locked keys
+/// can only be set by the defaults or system layers.
+fn merge_config_layers(
+    defaults: &Table,
+    system: Option<Table>,
+    user: &Table,
+    env: &Table,
+) -> (Table, BTreeMap<String, ConfigSource>) {
+    let mut sources = BTreeMap::new();
+    let mut merged = Table::new();
+
+    merge_layer(
+        &mut merged,
+        defaults,
+        ConfigSource::Default,
+        &[],
+        &mut sources,
+    );
+
+    let mut locked_keys = Vec::new();
+    if let Some(mut system) = system {
+        locked_keys = take_locked_keys(&mut system);
+
+        merge_layer(
+            &mut merged,
+            &system,
+            ConfigSource::System,
+            &[],
+            &mut sources,
+        );
+
+        for (key, source) in sources.iter_mut() {
+            if *source == ConfigSource::System && is_locked(key, &locked_keys) {
+                *source = ConfigSource::SystemLocked;
+            }
+        }
+    }
+
+    merge_layer(
+        &mut merged,
+        user,
+        ConfigSource::User,
+        &locked_keys,
+        &mut sources,
+    );
+    merge_layer(
+        &mut merged,
+        env,
+        ConfigSource::Environment,
+        &locked_keys,
+        &mut sources,
+    );
+
+    (merged, sources)
+}
+
+/// Keys (other than arrays, which are appended to) the user config sets to their default value while the system
+/// config sets them to something else. Earlier versions wrote every default to the user config, which would now
+/// shadow the system config.
+fn defaults_shadowing_system(defaults: &Table, system: &Table, user: &Table) -> Vec<String> {
+    let locked_keys = take_locked_keys(&mut system.clone());
+
+    flatten_table(user)
+        .into_iter()
+        .filter(|(key, value)| {
+            let key_parts: Vec<String> = key.split('.').map(|part| part.to_string()).collect();
+
+            !value.is_array()
+                && !is_locked(key, &locked_keys)
+                && get_value(defaults, &key_parts) == Some(value)
+                && get_value(system, &key_parts).is_some_and(|system_value| system_value != value)
+        })
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// Loads the config from its layers without writing anything, a missing user config is treated as empty
+pub fn load_config() -> Config {
     let config_path = get_config_file_path();
+    let system_config_path = get_system_config_file_path();
 
-    let created_new_config = ensure_config_exists(&config_path);
+    let defaults = default_config_table();
 
-    let mut config = if created_new_config {
-        Config::default()
-    } else {
-        let mut config_file_data = Vec::<u8>::new();
-        let mut config_file = File::open(&config_path)
-            .unwrap_or_else(|_| panic!("Opening config file at {:#?} to succeed", config_path));
-        config_file
-            .read_to_end(&mut config_file_data)
-            .expect("Reading config file to succeed");
+    // Only holds the keys the user has set, so the system config's values apply to everything else
+    let user = match config_path.try_exists() {
+        Ok(true) => read_config_table(&config_path),
+        _ => Table::new(),
+    };
 
-        toml::from_str(&String::from_utf8(config_file_data).expect("Valid utf8"))
-            .expect("Config file to be valid")
+    let system = match system_config_path.try_exists() {
+        Ok(true) => Some(read_config_table(&system_config_path)),
+        _ => None,
     };
 
-    write_config(&config_path, &config);
+    if let Some(system) = &system {
+        let shadowing = defaults_shadowing_system(&defaults, system, &user);
+
+        if !shadowing.is_empty() {
+            log!(
+                Warning,
+                "{config_path:#?} sets {} to the default value, overriding the system config. Earlier versions wrote \
+                 every default to it, remove these keys to use the system config's values instead",
+                shadowing.join(", ")
+            );
+        }
+    }
+
+    let (without_env, _) = merge_config_layers(&defaults, system.clone(), &user, &Table::new());
+    let env = env_overrides_table(env::vars(), &without_env);
+
+    let (merged, value_sources) = merge_config_layers(&defaults, system, &user, &env);
+
+    let mut config: Config = Value::Table(merged)
+        .try_into()
+        .expect("Config file to be valid");
 
     config.config_path = config_path.to_str().unwrap().to_string();
+    config.value_sources = value_sources;
+
+    config.sanitization.normalize();
+    config
+        .profiles
+        .values_mut()
+        .for_each(SanitizationProfile::normalize);
 
     config
 }
+
+/// Loads the config, first creating the user config (and its schema) if needed
+pub fn load_and_ensure_config() -> Config {
+    let config_path = get_config_file_path();
+
+    ensure_config_exists(&config_path, &default_config_table());
+    write_config_schema(&config_path);
+
+    load_config()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml_str: &str) -> Table {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn test_json_schema_describes_config() {
+        let schema: serde_json::Value = serde_json::from_str(&config_json_schema()).unwrap();
+
+        let properties = schema["properties"].as_object().unwrap();
+
+        assert!(properties.contains_key("log_level"));
+        assert!(properties.contains_key("log_file"));
+        assert!(!properties.contains_key("config_path"));
+        assert!(!properties.contains_key("value_sources"));
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let defaults =
+            table("log_level = \"INFO\"\nlog_file = \"default.log\"\n[nested]\na = 1\nb = 2");
+        let system = table("log_level = \"WARNING\"\n[nested]\na = 10");
+        let user = table("log_level = \"ERROR\"");
+        let env = table("[nested]\nb = 20");
+
+        let (merged, sources) = merge_config_layers(&defaults, Some(system), &user, &env);
+
+        assert_eq!(merged["log_level"].as_str(), Some("ERROR"));
+        assert_eq!(merged["log_file"].as_str(), Some("default.log"));
+        assert_eq!(merged["nested"]["a"].as_integer(), Some(10));
+        assert_eq!(merged["nested"]["b"].as_integer(), Some(20));
+
+        assert_eq!(sources["log_level"], ConfigSource::User);
+        assert_eq!(sources["log_file"], ConfigSource::Default);
+        assert_eq!(sources["nested.a"], ConfigSource::System);
+        assert_eq!(sources["nested.b"], ConfigSource::Environment);
+    }
+
+    #[test]
+    fn test_locked_keys_cannot_be_overridden() {
+        let defaults = table("log_level = \"INFO\"\n[nested]\na = 1");
+        let system = table("locked = [\"log_level\", \"nested\"]\nlog_level = \"DEBUG\"");
+        let user = table("log_level = \"ERROR\"\n[nested]\na = 2");
+        let env = table("log_level = \"WARNING\"");
+
+        let (merged, sources) = merge_config_layers(&defaults, Some(system), &user, &env);
+
+        assert_eq!(merged["log_level"].as_str(), Some("DEBUG"));
+        assert_eq!(merged["nested"]["a"].as_integer(), Some(1));
+        assert!(merged.get("locked").is_none());
+
+        assert_eq!(sources["log_level"], ConfigSource::SystemLocked);
+        assert_eq!(sources["nested.a"], ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let env = env_overrides_table(
+            [
+                ("CLIPBOARD_CLEANSE_LOG_LEVEL", "DEBUG"),
+                ("CLIPBOARD_CLEANSE_MACOS__POLL_INTERVAL_MS", "250"),
+                ("CLIPBOARD_CLEANSE_SOME__FLAG", "true"),
+                ("CLIPBOARD_CLEANSE_LOG_FILE", "2024"),
+                ("CLIPBOARD_CLEANSE_LOG_REDACTION", "true"),
+                ("CLIPBOARD_CLEANSE_HISTORY_SIZE", "lots"),
+                ("UNRELATED", "value"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+            &default_config_table(),
+        );
+
+        assert_eq!(
+            env,
+            table(
+                "log_level = \"DEBUG\"\nlog_file = \"2024\"\n[macos]\npoll_interval_ms = 250\n[some]\nflag = true"
+            )
+        );
+    }
+
+    #[test]
+    fn test_first_run_keeps_system_defaults() {
+        let defaults = default_config_table();
+        let system = table("log_level = \"WARNING\"\nallowlist = [\"*.corp.example\"]");
+        let user = table(&default_user_config(&defaults));
+
+        assert!(user.is_empty());
+
+        let (merged, sources) = merge_config_layers(&defaults, Some(system), &user, &Table::new());
+
+        assert_eq!(merged["log_level"].as_str(), Some("WARNING"));
+        assert_eq!(sources["log_level"], ConfigSource::System);
+        assert!(Value::Table(merged).try_into::<Config>().is_ok());
+    }
+
+    #[test]
+    fn test_defaults_shadowing_system() {
+        let defaults = default_config_table();
+        let system = table(
+            "log_level = \"WARNING\"\nhistory_size = 5\nbypass_window_ms = 0\nallowlist = [\"*.corp.example\"]\nlocked = [\"bypass_window_ms\"]",
+        );
+        // A user config written out in full by an earlier version
+        let mut user = defaults.clone();
+        user.insert("history_size".into(), Value::Integer(50));
+
+        assert_eq!(
+            defaults_shadowing_system(&defaults, &system, &user),
+            ["log_level"]
+        );
+        assert!(defaults_shadowing_system(&defaults, &system, &Table::new()).is_empty());
+    }
+
+    #[test]
+    fn test_arrays_are_appended_to_system_arrays() {
+        let defaults = table("allowlist = []\nexcluded = [\"default\"]");
+        let system = table(
+            "allowlist = [\"*.corp.example\"]\nlocked_list = [\"a\"]\nlocked = [\"locked_list\"]",
+        );
+        let user =
+            table("allowlist = [\"home.example\"]\nexcluded = [\"user\"]\nlocked_list = [\"b\"]");
+        let env = table("allowlist = [\"env.example\"]");
+
+        let (merged, _) = merge_config_layers(&defaults, Some(system), &user, &env);
+
+        assert_eq!(
+            merged,
+            table(
+                "allowlist = [\"*.corp.example\", \"home.example\", \"env.example\"]\nexcluded = [\"user\"]\nlocked_list = [\"a\"]"
+            )
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    backtrace::Backtrace,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{config::get_config_directory, log, logger};
+
+/// Redacted description of the loaded config, included in crash reports once it's known
+static CONFIG_DESCRIPTION: Mutex<Option<String>> = Mutex::new(None);
+
+fn get_crash_file_path() -> PathBuf {
+    get_config_directory().join("crash.log")
+}
+
+fn get_previous_crash_file_path() -> PathBuf {
+    get_config_directory().join("crash.previous.log")
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".into()
+    }
+}
+
+fn format_crash_report(
+    message: &str,
+    location: &str,
+    config_description: &str,
+    backtrace: &str,
+) -> String {
+    format!(
+        "{} {} panicked at {location}: {message}\nConfig: {config_description}\nBacktrace:\n{backtrace}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+    )
+}
+
+/// Installs a panic hook which writes a crash report, since release builds have no console to print panics to
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "unknown location".into());
+
+        let config_description = CONFIG_DESCRIPTION
+            .try_lock()
+            .ok()
+            .and_then(|description| description.clone())
+            .unwrap_or_else(|| "not loaded".into());
+
+        let report = format_crash_report(
+            &panic_message(info),
+            &location,
+            &config_description,
+            &Backtrace::force_capture().to_string(),
+        );
+
+        let crash_path = get_crash_file_path();
+
+        if let Err(err) = logger::write_crash_report(&crash_path, module_path!(), &report) {
+            eprintln!("Failed to write crash report to {crash_path:#?}: {err}");
+        }
+    }));
+}
+
+pub fn set_config_description(description: String) {
+    if let Ok(mut config_description) = CONFIG_DESCRIPTION.lock() {
+        *config_description = Some(description);
+    }
+}
+
+/// Title of the notification the platforms show when the previous session crashed
+pub const CRASH_NOTIFICATION_TITLE: &str = "Clipboard Cleanse crashed";
+
+/// Text of the notification the platforms show when the previous session crashed, pointing to the crash report
+pub fn crash_notification_text(report_path: &Path) -> String {
+    format!(
+        "The previous session crashed, the crash report was saved to {}",
+        report_path.display()
+    )
+}
+
+/// Reports a crash report left behind by the previous session, and moves it aside so it's only reported once.
+/// Returns where the report is, so the platform can notify the user since release builds have no console.
+pub fn report_previous_crash() -> Option<PathBuf> {
+    let crash_path = get_crash_file_path();
+
+    if !crash_path.exists() {
+        return None;
+    }
+
+    let previous_crash_path = get_previous_crash_file_path();
+
+    match fs::rename(&crash_path, &previous_crash_path) {
+        Ok(_) => {
+            log!(
+                Warning,
+                "The previous session crashed, see {previous_crash_path:#?} for details"
+            );
+            Some(previous_crash_path)
+        }
+        Err(err) => {
+            log!(
+                Warning,
+                "The previous session crashed, see {crash_path:#?} for details (failed to move it: {err})"
+            );
+            Some(crash_path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_crash_report() {
+        let report = format_crash_report(
+            "called `Option::unwrap()` on a `None` value",
+            "src/main.rs:1:1",
+            "\"config.toml\" with nothing set",
+            "0: main",
+        );
+
+        assert!(report.starts_with(&format!(
+            "clipboard_cleanse {} panicked at src/main.rs:1:1: called `Option::unwrap()` on a `None` value\n",
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(report.contains("Config: \"config.toml\" with nothing set\n"));
+        assert!(report.ends_with("Backtrace:\n0: main"));
+    }
+}
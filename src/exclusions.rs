@@ -0,0 +1,193 @@
+//! Decides whether a clipboard payload should be left alone entirely, e.g. because it's code or test data in which
+//! rewriting URLs would corrupt it.
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::log;
+
+/// Copied text which is never sanitized
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq)]
+pub struct ExclusionConfig {
+    /// Regular expressions, text matching any of them is left untouched
+    #[serde(default)]
+    pub patterns: Vec<String>,
+
+    /// Leave text which parses as a JSON object or array untouched
+    #[serde(default)]
+    pub skip_json: bool,
+
+    /// Leave text which looks like source code untouched
+    #[serde(default)]
+    pub skip_code: bool,
+
+    /// Leave text shorter than this many characters untouched
+    #[serde(default)]
+    pub min_length: Option<usize>,
+
+    /// Leave text longer than this many characters untouched
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+/// Compiled form of [`ExclusionConfig`]
+#[derive(Debug, Default)]
+pub struct Exclusions {
+    config: ExclusionConfig,
+    patterns: Vec<Regex>,
+}
+
+/// Lines which end or start like statements, blocks or declarations in common programming languages
+const CODE_LINE_ENDINGS: &[&str] = &[";", "{", "}", "=>", "):"];
+const CODE_LINE_STARTS: &[&str] = &[
+    "fn ",
+    "pub ",
+    "let ",
+    "const ",
+    "var ",
+    "function ",
+    "def ",
+    "class ",
+    "import ",
+    "from ",
+    "return ",
+    "if ",
+    "for ",
+    "while ",
+    "#include",
+    "//",
+    "/*",
+    "use ",
+];
+
+fn looks_like_json(text: &str) -> bool {
+    let text = text.trim();
+
+    let is_container = (text.starts_with('{') && text.ends_with('}'))
+        || (text.starts_with('[') && text.ends_with(']'));
+
+    is_container && serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+/// Whether most of the (at least two) non-empty lines look like code
+fn looks_like_code(text: &str) -> bool {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    if lines.len() < 2 {
+        return false;
+    }
+
+    let code_lines = lines
+        .iter()
+        .filter(|l| {
+            CODE_LINE_ENDINGS.iter().any(|e| l.ends_with(e))
+                || CODE_LINE_STARTS.iter().any(|s| l.starts_with(s))
+        })
+        .count();
+
+    code_lines * 2 >= lines.len()
+}
+
+impl Exclusions {
+    pub fn new(config: &ExclusionConfig) -> Self {
+        let patterns = config
+            .patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    log!(
+                        Error,
+                        "Ignoring invalid exclusion pattern {pattern:?}: {err}"
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            patterns,
+        }
+    }
+
+    /// Why the text should be left untouched, if it should be
+    pub fn exclusion_reason(&self, text: &str) -> Option<String> {
+        let length = text.chars().count();
+
+        if let Some(min_length) = self.config.min_length {
+            if length < min_length {
+                return Some(format!("is shorter than {min_length} characters"));
+            }
+        }
+
+        if let Some(max_length) = self.config.max_length {
+            if length > max_length {
+                return Some(format!("is longer than {max_length} characters"));
+            }
+        }
+
+        if let Some(pattern) = self.patterns.iter().find(|p| p.is_match(text)) {
+            return Some(format!("matches exclusion pattern {:?}", pattern.as_str()));
+        }
+
+        if self.config.skip_json && looks_like_json(text) {
+            return Some("looks like JSON".into());
+        }
+
+        if self.config.skip_code && looks_like_code(text) {
+            return Some("looks like code".into());
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusion_reasons() {
+        let exclusions = Exclusions::new(&ExclusionConfig {
+            patterns: vec![r"^(GET|POST) /".into(), "(invalid".into()],
+            skip_json: true,
+            skip_code: true,
+            min_length: Some(8),
+            max_length: Some(200),
+        });
+
+        for (text, expected) in [
+            ("a.co?s=1", None),
+            ("x.co", Some("is shorter than 8 characters")),
+            (
+                &"https://example.com/ ".repeat(20),
+                Some("is longer than 200 characters"),
+            ),
+            (
+                "GET /search?utm_source=x HTTP/1.1",
+                Some("matches exclusion pattern \"^(GET|POST) /\""),
+            ),
+            (
+                r#"{"url": "https://example.com/?utm_source=fixture"}"#,
+                Some("looks like JSON"),
+            ),
+            (
+                "let url = \"https://example.com/?utm_source=x\";\nfetch(url);",
+                Some("looks like code"),
+            ),
+            ("Check this out:\nhttps://example.com/?utm_source=x", None),
+        ] {
+            assert_eq!(
+                exclusions.exclusion_reason(text).as_deref(),
+                expected,
+                "{text:?}"
+            );
+        }
+    }
+}
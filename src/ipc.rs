@@ -0,0 +1,254 @@
+//! Lets command-line invocations control the running app over a localhost TCP socket. The port of the running app and
+//! a random token are written to a file in the config directory that only the current user can read, requests are a
+//! JSON encoded [`Request`] carrying that token and responses are a JSON encoded `Result<String, String>`.
+
+use std::{
+    collections::hash_map::RandomState,
+    fs::{self, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_core::{AppCore, SharedAppCore},
+    config::get_config_directory,
+    log,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn get_port_file_path() -> PathBuf {
+    get_config_directory().join("ipc_port")
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    token: String,
+    args: Vec<String>,
+}
+
+/// Generates a random token from the OS seeded keys of the standard library's hasher, to avoid depending on a random
+/// number generator crate
+fn generate_token() -> String {
+    (0..4)
+        .map(|i| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_usize(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Compares tokens in constant time so the response time doesn't reveal how much of a guess was right
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Writes the port and token, making sure other users can't read the file. The config directory is already private
+/// to the user on Windows
+fn write_port_file(port: u16, token: &str) -> io::Result<()> {
+    let path = get_port_file_path();
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        // The mode only applies when creating the file, so also restrict a file left over from a previous run
+        if path.exists() {
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    options
+        .open(path)?
+        .write_all(format!("{port}\n{token}").as_bytes())
+}
+
+fn parse_request(request: &str, token: &str) -> Result<Vec<String>, String> {
+    let request = serde_json::from_str::<Request>(request)
+        .map_err(|err| format!("Malformed request: {err}"))?;
+
+    if !tokens_match(&request.token, token) {
+        return Err("Invalid IPC token".into());
+    }
+
+    Ok(request.args)
+}
+
+/// Executes an app command against the given core, used both by the IPC server and when the app isn't running
+pub fn execute_command(core: &mut AppCore, args: &[&str]) -> Result<String, String> {
+    match args {
+        ["profile"] | ["profile", "list"] => Ok(core
+            .profile_menu_entries()
+            .into_iter()
+            .map(|e| format!("{} {}", if e.active { "*" } else { " " }, e.label))
+            .collect::<Vec<String>>()
+            .join("\n")),
+        ["profile", "use", profile] => core
+            .set_active_profile(Some(profile))
+            .map(|_| format!("Switched to profile {profile:?}")),
+        ["profile", "clear"] => core
+            .set_active_profile(None)
+            .map(|_| "Switched to no profile".into()),
+        ["pause"] => {
+            core.pause(None)?;
+            Ok(core.describe_pause())
+        }
+        ["pause", minutes] => {
+            let secs = minutes
+                .parse::<u64>()
+                .ok()
+                .and_then(|minutes| minutes.checked_mul(60))
+                .ok_or_else(|| format!("Invalid number of minutes: {minutes:?}"))?;
+
+            core.pause(Some(Duration::from_secs(secs)))?;
+            Ok(core.describe_pause())
+        }
+        ["resume"] => {
+            core.resume();
+            Ok(core.describe_pause())
+        }
+        ["history"] | ["history", "list"] => {
+            Ok(core.watcher.describe_history(core.config.log_redaction))
+        }
+        ["history", "restore", entry_number] => {
+            let entry_number = entry_number
+                .parse()
+                .map_err(|_| format!("Invalid history entry number: {entry_number:?}"))?;
+
+            core.watcher
+                .request_restore(entry_number)
+                .map(|_| format!("Restoring the original text of history entry {entry_number}"))
+        }
+        _ => Err(format!("Unknown command: {:?}", args.join(" "))),
+    }
+}
+
+fn handle_connection(core: &SharedAppCore, token: &str, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let response = match parse_request(&request, token) {
+        Ok(args) => {
+            let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+            log!(Debug, "Received IPC command: {args:?}");
+
+            execute_command(&mut core.lock().unwrap(), &args)
+        }
+        Err(err) => {
+            log!(Warning, "Rejected IPC request: {err}");
+            Err(err)
+        }
+    };
+
+    (&stream).write_all(serde_json::to_string(&response)?.as_bytes())
+}
+
+/// Starts listening for commands on a background thread
+pub fn start_server(core: SharedAppCore) {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log!(Error, "Failed to start IPC server: {err}");
+            return;
+        }
+    };
+
+    let port = listener.local_addr().unwrap().port();
+    let token = generate_token();
+
+    if let Err(err) = write_port_file(port, &token) {
+        log!(Error, "Failed to write IPC port file: {err}");
+        return;
+    }
+
+    log!(Debug, "IPC server listening on port {port}");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle_connection(&core, &token, stream));
+
+            if let Err(err) = result {
+                log!(Warning, "Failed to handle IPC connection: {err}");
+            }
+        }
+    });
+}
+
+/// Sends a command to the running app, erroring with [`io::ErrorKind::NotConnected`] if it doesn't appear to be running
+pub fn send_command(args: &[&str]) -> io::Result<Result<String, String>> {
+    let not_running = |_| io::Error::from(io::ErrorKind::NotConnected);
+
+    let port_file = fs::read_to_string(get_port_file_path()).map_err(not_running)?;
+    let (port, token) = port_file
+        .trim()
+        .split_once('\n')
+        .and_then(|(port, token)| Some((port.parse::<u16>().ok()?, token)))
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+    let stream =
+        TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, port)), TIMEOUT)
+            .map_err(not_running)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    let request = Request {
+        token: token.to_owned(),
+        args: args.iter().map(|&a| a.to_owned()).collect(),
+    };
+    (&stream).write_all(format!("{}\n", serde_json::to_string(&request)?).as_bytes())?;
+
+    let mut response = String::new();
+    BufReader::new(&stream).read_line(&mut response)?;
+
+    Ok(serde_json::from_str(&response)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_require_the_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_token());
+
+        let request = |token: &str| {
+            serde_json::to_string(&Request {
+                token: token.into(),
+                args: vec!["pause".into(), "5".into()],
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            parse_request(&request(&token), &token),
+            Ok(vec!["pause".into(), "5".into()])
+        );
+        assert_eq!(
+            parse_request(&request(&generate_token()), &token),
+            Err("Invalid IPC token".into())
+        );
+        assert_eq!(
+            parse_request(&request(""), &token),
+            Err("Invalid IPC token".into())
+        );
+        assert!(parse_request(r#"["pause", "5"]"#, &token).is_err());
+    }
+}
@@ -1,14 +1,33 @@
 use std::{
     fmt::{Debug, Display},
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
+    hash::{DefaultHasher, Hash, Hasher},
     io::{self, Write},
+    path::{Path, PathBuf},
     sync::{LazyLock, RwLock},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
 static LOGGER: LazyLock<RwLock<Logger>> = LazyLock::new(|| RwLock::new(Logger::default()));
 
+/// URLs with an explicit scheme (without trailing punctuation), which is how clipboard contents usually end up in log
+/// messages
+static LOGGED_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"[a-zA-Z][a-zA-Z0-9+.\-]*://[^\s"'<>]*[^\s"'<>.,;:!?)]"#).unwrap()
+});
+
+/// Forwards records from the `log` facade (used by the [`log!`](crate::log) macro and dependencies) to [`LOGGER`]
+static BRIDGE: Bridge = Bridge;
+
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum LogLevel {
+    Trace,
     Debug,
     Info,
     Warning,
@@ -20,24 +39,50 @@ impl TryFrom<&str> for LogLevel {
 
     fn try_from(value: &str) -> Result<Self, <LogLevel as TryFrom<&str>>::Error> {
         match value.to_ascii_uppercase().as_str() {
+            "TRACE" => Ok(Self::Trace),
             "DEBUG" => Ok(Self::Debug),
             "INFO" => Ok(Self::Info),
-            "WARNING" => Ok(Self::Warning),
+            "WARNING" | "WARN" => Ok(Self::Warning),
             "ERROR" => Ok(Self::Error),
             _ => Err(format!(
-                "Expected one of DEBUG, INFO, WARNING, or ERROR, but got {:#?} instead",
+                "Expected one of TRACE, DEBUG, INFO, WARNING, or ERROR, but got {:#?} instead",
                 value
             )),
         }
     }
 }
 
+impl From<log::Level> for LogLevel {
+    fn from(value: log::Level) -> Self {
+        match value {
+            log::Level::Trace => Self::Trace,
+            log::Level::Debug => Self::Debug,
+            log::Level::Info => Self::Info,
+            log::Level::Warn => Self::Warning,
+            log::Level::Error => Self::Error,
+        }
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Trace => Self::Trace,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Info => Self::Info,
+            LogLevel::Warning => Self::Warn,
+            LogLevel::Error => Self::Error,
+        }
+    }
+}
+
 impl Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
             match *self {
+                Self::Trace => "TRACE",
                 Self::Debug => "DEBUG",
                 Self::Info => "INFO",
                 Self::Warning => "WARNING",
@@ -47,33 +92,446 @@ impl Display for LogLevel {
     }
 }
 
-#[derive(Debug)]
-pub struct Logger {
-    threshold: LogLevel,
-    file: Option<File>,
+/// Which records get logged, parsed from `RUST_LOG`-style directives like `info,clipboard_cleanse::ipc=debug,url=off`:
+/// a bare level sets the default, `<target>=<level>` sets the level for a module path (and its children) and `off`
+/// disables logging entirely
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    /// `None` when logging is turned off
+    default: Option<LogLevel>,
+    directives: Vec<(String, Option<LogLevel>)>,
 }
 
-impl Default for Logger {
+impl Default for LogFilter {
     fn default() -> Self {
         Self {
-            threshold: LogLevel::Debug,
-            file: None,
+            default: Some(LogLevel::Debug),
+            directives: Vec::new(),
         }
     }
 }
 
-pub fn set_threshold(threshold: LogLevel) {
-    let mut logger = LOGGER.write().unwrap();
-    logger.threshold = threshold;
+impl TryFrom<&str> for LogFilter {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        fn parse_level(level: &str) -> Result<Option<LogLevel>, String> {
+            match level.trim().to_ascii_uppercase().as_str() {
+                "OFF" => Ok(None),
+                level => LogLevel::try_from(level).map(Some),
+            }
+        }
+
+        let mut filter = Self {
+            default: Some(LogLevel::Info),
+            directives: Vec::new(),
+        };
+
+        for directive in value.split(',').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => filter
+                    .directives
+                    .push((target.trim().to_string(), parse_level(level)?)),
+                None => match parse_level(directive) {
+                    Ok(level) => filter.default = level,
+                    // A bare module path enables every level for it, like RUST_LOG
+                    Err(_)
+                        if directive
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || "_:".contains(c)) =>
+                    {
+                        filter
+                            .directives
+                            .push((directive.to_string(), Some(LogLevel::Trace)))
+                    }
+                    Err(err) => return Err(err),
+                },
+            }
+        }
+
+        Ok(filter)
+    }
 }
 
-pub fn set_file(file_path: Option<&str>) {
-    let file = file_path.map(|fp| {
-        OpenOptions::new()
+impl LogFilter {
+    /// Logs messages of at least the level for every target
+    pub fn at_level(level: LogLevel) -> Self {
+        Self {
+            default: Some(level),
+            directives: Vec::new(),
+        }
+    }
+
+    /// Level for the given target, using the most specific matching directive
+    fn level_for(&self, target: &str) -> Option<&LogLevel> {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default.as_ref(), |(_, level)| level.as_ref())
+    }
+
+    pub fn enabled(&self, level: &LogLevel, target: &str) -> bool {
+        self.level_for(target)
+            .is_some_and(|threshold| level >= threshold)
+    }
+
+    /// Most verbose level any target may log at, lets the `log` facade skip formatting anything more verbose
+    fn max_level_filter(&self) -> log::LevelFilter {
+        [&self.default]
+            .into_iter()
+            .chain(self.directives.iter().map(|(_, level)| level))
+            .filter_map(|level| level.clone())
+            .map(|level| log::Level::from(level).to_level_filter())
+            .max()
+            .unwrap_or(log::LevelFilter::Off)
+    }
+}
+
+/// How much of the URLs (and other clipboard contents) may end up in the logs
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRedaction {
+    /// Only hosts and parameter names are logged, values & paths are elided
+    #[default]
+    Strict,
+    /// Like strict, but values & paths are replaced by a short hash so they can be correlated
+    Hashed,
+    /// Everything is logged as-is, which may include passwords or personal data from the clipboard
+    Off,
+}
+
+/// Short, stable hash of a value which lets values be correlated across log lines without logging them
+pub fn hash_for_log(value: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+fn redact_url(url: &str, redaction: LogRedaction) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return "[redacted url]".into();
+    };
+
+    let elide = |value: &str| match redaction {
+        LogRedaction::Hashed => format!("#{}", hash_for_log(value)),
+        _ => "[redacted]".into(),
+    };
+
+    let mut redacted = format!("{}://{}", parsed.scheme(), parsed.host_str().unwrap_or(""));
+
+    if !matches!(parsed.path(), "" | "/") {
+        redacted += &format!("/{}", elide(parsed.path()));
+    }
+
+    if parsed.query().is_some() {
+        redacted += "?";
+        redacted += &parsed
+            .query_pairs()
+            .map(|(key, value)| format!("{key}={}", elide(&value)))
+            .collect::<Vec<String>>()
+            .join("&");
+    }
+
+    if let Some(fragment) = parsed.fragment() {
+        redacted += &format!("#{}", elide(fragment));
+    }
+
+    redacted
+}
+
+/// Redacts the URLs in a log message according to the redaction level
+pub fn redact_urls(text: &str, redaction: LogRedaction) -> String {
+    match redaction {
+        LogRedaction::Off => text.to_string(),
+        _ => LOGGED_URL_REGEX
+            .replace_all(text, |captures: &regex::Captures| {
+                redact_url(&captures[0], redaction)
+            })
+            .into_owned(),
+    }
+}
+
+/// Format of the lines written to the log file
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// `<timestamp> <level> [<thread>] <target>: <message>`
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp`, `level`, `thread`, `target` and `message` keys
+    Json,
+}
+
+/// How the log file is opened and when it is rotated
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLogOptions {
+    /// Keep the existing contents of the log file instead of truncating it
+    pub append: bool,
+    pub format: LogFormat,
+    /// Rotate once the file would grow beyond this many bytes
+    pub max_size_bytes: Option<u64>,
+    /// Rotate once the file has been written to for longer than this
+    pub max_age: Option<Duration>,
+    /// How many rotated files (`<log_file>.1`, `<log_file>.2`, ...) are kept
+    pub retention: usize,
+}
+
+impl Default for FileLogOptions {
+    fn default() -> Self {
+        Self {
+            append: true,
+            format: LogFormat::Text,
+            max_size_bytes: None,
+            max_age: None,
+            retention: 5,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened_at: SystemTime,
+    options: FileLogOptions,
+}
+
+impl LogFile {
+    fn open(path: &Path, options: FileLogOptions) -> io::Result<Self> {
+        let file = OpenOptions::new()
             .create(true)
-            .truncate(true)
+            .append(options.append)
+            .truncate(!options.append)
             .write(true)
-            .open(fp)
+            .open(path)?;
+
+        let metadata = file.metadata()?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            opened_at: match options.append {
+                true => metadata.created().unwrap_or_else(|_| SystemTime::now()),
+                false => SystemTime::now(),
+            },
+            file,
+            options,
+        })
+    }
+
+    fn needs_rotation(&self, incoming_bytes: u64, now: SystemTime) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+
+        let too_big = self
+            .options
+            .max_size_bytes
+            .is_some_and(|max| self.size + incoming_bytes > max);
+
+        let too_old = self.options.max_age.is_some_and(|max_age| {
+            now.duration_since(self.opened_at)
+                .is_ok_and(|age| age > max_age)
+        });
+
+        too_big || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        rotate_files(&self.path, self.options.retention)?;
+
+        *self = Self::open(
+            &self.path,
+            FileLogOptions {
+                append: false,
+                ..self.options.clone()
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.needs_rotation(line.len() as u64, SystemTime::now()) {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+}
+
+fn rotated_file_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{index}"));
+    PathBuf::from(rotated)
+}
+
+/// Shifts `<path>` to `<path>.1`, `<path>.1` to `<path>.2`, etc. deleting whatever would exceed `retention`
+fn rotate_files(path: &Path, retention: usize) -> io::Result<()> {
+    if retention == 0 {
+        return fs::remove_file(path);
+    }
+
+    let oldest = rotated_file_path(path, retention);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+
+    for index in (1..retention).rev() {
+        let from = rotated_file_path(path, index);
+
+        if from.exists() {
+            fs::rename(&from, rotated_file_path(path, index + 1))?;
+        }
+    }
+
+    fs::rename(path, rotated_file_path(path, 1))
+}
+
+/// Formats a timestamp as RFC 3339 in UTC with millisecond precision, e.g. `2024-10-13T15:09:26.123Z`
+pub fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    let seconds = since_epoch.as_secs();
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil from days, see https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+fn current_thread_name() -> String {
+    let current = thread::current();
+
+    match current.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", current.id()),
+    }
+}
+
+struct LogLine<'a> {
+    timestamp: &'a str,
+    level: &'a LogLevel,
+    thread: &'a str,
+    target: &'a str,
+    text: &'a str,
+}
+
+fn format_line(format: LogFormat, line: &LogLine) -> String {
+    let LogLine {
+        timestamp,
+        level,
+        thread,
+        target,
+        text,
+    } = line;
+
+    match format {
+        LogFormat::Text => format!("{timestamp} {level} [{thread}] {target}: {text}\n"),
+        LogFormat::Json => {
+            serde_json::json!({
+                "timestamp": timestamp,
+                "level": level.to_string(),
+                "thread": thread,
+                "target": target,
+                "message": text,
+            })
+            .to_string()
+                + "\n"
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Logger {
+    filter: LogFilter,
+    redaction: LogRedaction,
+    file: Option<LogFile>,
+    /// Writes to stderr instead of stdout, so logs don't mix with the output of command-line invocations
+    to_stderr: bool,
+}
+
+struct Bridge;
+
+impl log::Log for Bridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let logger = LOGGER.read().unwrap();
+        logger
+            .filter
+            .enabled(&metadata.level().into(), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            write_record(
+                record.level().into(),
+                record.target(),
+                &record.args().to_string(),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger as the `log` facade's logger, until this is called nothing gets logged
+pub fn install() {
+    if log::set_logger(&BRIDGE).is_ok() {
+        log::set_max_level(LogFilter::default().max_level_filter());
+    }
+}
+
+pub fn set_redaction(redaction: LogRedaction) {
+    let mut logger = LOGGER.write().unwrap();
+    logger.redaction = redaction;
+}
+
+pub fn redaction() -> LogRedaction {
+    LOGGER.read().unwrap().redaction
+}
+
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level_filter());
+
+    let mut logger = LOGGER.write().unwrap();
+    logger.filter = filter;
+}
+
+pub fn set_stderr(to_stderr: bool) {
+    let mut logger = LOGGER.write().unwrap();
+    logger.to_stderr = to_stderr;
+}
+
+pub fn set_file(file_path: Option<&str>, options: FileLogOptions) {
+    let file = file_path.map(|fp| {
+        LogFile::open(Path::new(fp), options.clone())
             .unwrap_or_else(|err| panic!("Failed to open log file {fp} due to {err}"))
     });
 
@@ -81,34 +539,240 @@ pub fn set_file(file_path: Option<&str>) {
     logger.file = file;
 }
 
-pub fn log(level: LogLevel, text: &str) {
-    let skip_log = {
-        let logger = LOGGER.read().unwrap();
-        level < logger.threshold
+fn write_record(level: LogLevel, target: &str, text: &str) {
+    let mut logger = LOGGER.write().unwrap();
+
+    let timestamp = format_timestamp(SystemTime::now());
+    let thread = current_thread_name();
+    let text = redact_urls(text, logger.redaction);
+
+    let line = LogLine {
+        timestamp: &timestamp,
+        level: &level,
+        thread: &thread,
+        target,
+        text: &text,
     };
 
-    if skip_log {
-        return;
+    let formatted = format_line(LogFormat::Text, &line);
+    if logger.to_stderr {
+        io::stderr().write_all(formatted.as_bytes()).unwrap();
+    } else {
+        io::stdout().write_all(formatted.as_bytes()).unwrap();
+        io::stdout().flush().unwrap();
     }
 
-    let mut logger = LOGGER.write().unwrap();
+    if let Some(file) = &mut logger.file {
+        let formatted = match file.options.format {
+            LogFormat::Text => formatted,
+            format => format_line(format, &line),
+        };
+
+        if let Err(err) = file.write_line(&formatted) {
+            io::stderr()
+                .write_all(format!("Failed to write to log file: {err}\n").as_bytes())
+                .unwrap();
+        }
+    }
+}
 
-    let formatted = format!("{level}: {text}\n");
-    io::stdout().write_all(formatted.as_bytes()).unwrap();
-    io::stdout().flush().unwrap();
+/// Writes a crash report to the crash file, stderr and the log file (if any). The logger's lock is only tried since
+/// the panic may have happened while it was held.
+pub fn write_crash_report(crash_path: &Path, target: &str, text: &str) -> io::Result<()> {
+    let redaction = LOGGER
+        .try_read()
+        .map(|logger| logger.redaction)
+        .unwrap_or_default();
 
-    if let Some(file) = &mut logger.file {
-        file.write_all(formatted.as_bytes()).unwrap();
-        file.flush().unwrap();
+    let timestamp = format_timestamp(SystemTime::now());
+    let thread = current_thread_name();
+    let text = redact_urls(text, redaction);
+
+    let line = LogLine {
+        timestamp: &timestamp,
+        level: &LogLevel::Error,
+        thread: &thread,
+        target,
+        text: &text,
+    };
+
+    let formatted = format_line(LogFormat::Text, &line);
+    let _ = io::stderr().write_all(formatted.as_bytes());
+
+    if let Ok(mut logger) = LOGGER.try_write() {
+        if let Some(file) = &mut logger.file {
+            let _ = file.write_line(&format_line(file.options.format, &line));
+        }
     }
+
+    fs::write(crash_path, formatted)
 }
 
+/// Thin wrapper around the `log` facade's macro using our level names, e.g. `log!(Warning, "Uh oh: {err}")`
 #[macro_export]
 macro_rules! log {
     ($log_level:ident, $($arg:tt)*) => {
-        {
-            use $crate::logger::{log, LogLevel};
-            log(LogLevel::$log_level, &format!($($arg)*));
-        }
+        ::log::log!(
+            <::log::Level>::from($crate::logger::LogLevel::$log_level),
+            $($arg)*
+        )
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "clipboard_cleanse_logger_test_{}_{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("test.log")
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        for (seconds, millis, expected) in [
+            (0, 0, "1970-01-01T00:00:00.000Z"),
+            (951_782_400, 5, "2000-02-29T00:00:00.005Z"),
+            (1_728_832_166, 123, "2024-10-13T15:09:26.123Z"),
+        ] {
+            let time = UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_millis(millis);
+            assert_eq!(format_timestamp(time), expected);
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let line = format_line(
+            LogFormat::Json,
+            &LogLine {
+                timestamp: "2024-10-13T15:09:26.123Z",
+                level: &LogLevel::Info,
+                thread: "main",
+                target: "clipboard_cleanse::sanitization",
+                text: "Sanitized \"copied\" text!",
+            },
+        );
+
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["level"], "INFO");
+        assert_eq!(parsed["target"], "clipboard_cleanse::sanitization");
+        assert_eq!(parsed["message"], "Sanitized \"copied\" text!");
+        assert!(line.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_redact_urls() {
+        const MESSAGE: &str =
+            "Failed to parse https://shop.example.com/orders/1234?token=hunter2&ref=mail#details, retrying";
+
+        assert_eq!(
+            redact_urls(MESSAGE, LogRedaction::Strict),
+            "Failed to parse https://shop.example.com/[redacted]?token=[redacted]&ref=[redacted]#[redacted], retrying"
+        );
+
+        let hashed = redact_urls(MESSAGE, LogRedaction::Hashed);
+        assert!(!hashed.contains("hunter2") && !hashed.contains("1234"));
+        assert!(hashed.contains(&format!("token=#{}", hash_for_log("hunter2"))));
+
+        assert_eq!(redact_urls(MESSAGE, LogRedaction::Off), MESSAGE);
+        assert_eq!(
+            redact_urls("No URLs here", LogRedaction::Strict),
+            "No URLs here"
+        );
+    }
+
+    #[test]
+    fn test_log_filter_directives() {
+        let filter = LogFilter::try_from(
+            "warning,clipboard_cleanse=info,clipboard_cleanse::ipc=trace,url=off",
+        )
+        .unwrap();
+
+        for (level, target, expected) in [
+            (LogLevel::Info, "regex", false),
+            (LogLevel::Warning, "regex", true),
+            (LogLevel::Info, "clipboard_cleanse::config", true),
+            (LogLevel::Debug, "clipboard_cleanse::config", false),
+            (LogLevel::Trace, "clipboard_cleanse::ipc", true),
+            (LogLevel::Trace, "clipboard_cleanse::ipc_other", false),
+            (LogLevel::Error, "url", false),
+        ] {
+            assert_eq!(filter.enabled(&level, target), expected, "{level} {target}");
+        }
+
+        assert_eq!(filter.max_level_filter(), log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_log_filter_plain_level_is_backwards_compatible() {
+        let filter = LogFilter::try_from("WARNING").unwrap();
+
+        assert!(!filter.enabled(&LogLevel::Info, "clipboard_cleanse"));
+        assert!(filter.enabled(&LogLevel::Warning, "clipboard_cleanse"));
+        assert!(LogFilter::try_from("clipboard_cleanse=LOUD").is_err());
+    }
+
+    #[test]
+    fn test_append_mode_keeps_existing_lines() {
+        let path = temp_log_path("append");
+        fs::write(&path, "old line\n").unwrap();
+
+        let mut log_file = LogFile::open(&path, FileLogOptions::default()).unwrap();
+        log_file.write_line("new line\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old line\nnew line\n");
+    }
+
+    #[test]
+    fn test_size_based_rotation_with_retention() {
+        let path = temp_log_path("rotation");
+
+        let mut log_file = LogFile::open(
+            &path,
+            FileLogOptions {
+                max_size_bytes: Some(10),
+                retention: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        for line in ["first!!\n", "second!\n", "third!!\n", "fourth!\n"] {
+            log_file.write_line(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth!\n");
+        assert_eq!(
+            fs::read_to_string(rotated_file_path(&path, 1)).unwrap(),
+            "third!!\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_file_path(&path, 2)).unwrap(),
+            "second!\n"
+        );
+        assert!(!rotated_file_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_age_based_rotation() {
+        let path = temp_log_path("age");
+
+        let mut log_file = LogFile::open(
+            &path,
+            FileLogOptions {
+                max_age: Some(Duration::from_secs(60)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        log_file.write_line("line\n").unwrap();
+
+        assert!(!log_file.needs_rotation(0, log_file.opened_at + Duration::from_secs(30)));
+        assert!(log_file.needs_rotation(0, log_file.opened_at + Duration::from_secs(61)));
+    }
+}
@@ -9,6 +9,7 @@ mod ns_pasteboard;
 mod ns_size;
 mod ns_status_bar;
 mod ns_url;
+mod ns_user_notification;
 
 pub use ns_application::NSApplication;
 pub use ns_button::{NSButton, NSButtonMethods};
@@ -17,7 +18,8 @@ pub use ns_error::NSError;
 pub use ns_file_manager::NSFileManager;
 pub use ns_image::{NSImage, NSImageResizingMode};
 pub use ns_menu::{NSMenu, NSMenuItem, NSMenuItemBadge};
-pub use ns_pasteboard::NSPasteboard;
+pub use ns_pasteboard::{pasteboard_type_to_string, NSPasteboard, NSPasteboardType};
 pub use ns_size::NSSize;
 pub use ns_status_bar::{NSStatusBar, NSStatusItem};
 pub use ns_url::NSURL;
+pub use ns_user_notification::{NSUserNotification, NSUserNotificationCenter};
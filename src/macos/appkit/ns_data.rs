@@ -1,6 +1,6 @@
 use std::ffi::c_void;
 
-use objc2::{extern_class, msg_send_id, mutability, rc::Retained, ClassType};
+use objc2::{extern_class, msg_send, msg_send_id, mutability, rc::Retained, ClassType};
 use objc2_foundation::NSObject;
 
 extern_class!(
@@ -14,8 +14,22 @@ extern_class!(
 );
 
 impl NSData {
-    pub fn init_with_bytes<const N: usize>(data: &[u8; N]) -> Retained<Self> {
+    pub fn init_with_bytes(data: &[u8]) -> Retained<Self> {
+        let length = data.len();
         let data = data.as_ptr() as *mut c_void;
-        unsafe { msg_send_id![Self::alloc(), initWithBytes:data, length:N] }
+        unsafe { msg_send_id![Self::alloc(), initWithBytes:data, length:length] }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        unsafe {
+            let length: usize = msg_send![self, length];
+            let bytes: *const u8 = msg_send![self, bytes];
+
+            if bytes.is_null() || length == 0 {
+                return Vec::new();
+            }
+
+            std::slice::from_raw_parts(bytes, length).to_vec()
+        }
     }
 }
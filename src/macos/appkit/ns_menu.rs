@@ -1,10 +1,12 @@
+use std::cell::RefCell;
+
 use objc2::rc::Retained;
 use objc2::runtime::NSObject;
 use objc2::{
     class, declare_class, extern_class, msg_send, msg_send_id, mutability, sel, ClassType,
     DeclaredClass,
 };
-use objc2_foundation::{MainThreadMarker, NSString};
+use objc2_foundation::{MainThreadMarker, NSInteger, NSString};
 
 extern_class!(
     #[derive(Debug)]
@@ -46,6 +48,8 @@ extern_class!(
 pub struct NSMenuItemIvars {
     #[allow(clippy::type_complexity)]
     action: Option<Box<dyn Fn(&NSMenuItem)>>,
+    #[allow(clippy::type_complexity)]
+    on_validate: RefCell<Option<Box<dyn Fn(&NSMenuItem)>>>,
 }
 
 declare_class!(
@@ -69,6 +73,16 @@ declare_class!(
                 action(self);
             }
         }
+
+        // Called by menus with auto-enabling items every time before they're shown, as the item is its own target
+        #[method(validateMenuItem:)]
+        fn validate_menu_item(&self, _item: &NSMenuItemRaw) -> bool {
+            if let Some(on_validate) = &*self.ivars().on_validate.borrow() {
+                on_validate(self);
+            }
+
+            true
+        }
     }
 );
 
@@ -81,6 +95,7 @@ impl NSMenuItem {
     ) -> Retained<Self> {
         let this = mtm.alloc::<NSMenuItem>().set_ivars(NSMenuItemIvars {
             action: Some(action),
+            on_validate: RefCell::new(None),
         });
 
         let this: Retained<NSMenuItem> = unsafe {
@@ -108,6 +123,21 @@ impl NSMenuItem {
     pub fn set_badge(&self, badge: &NSMenuItemBadge) {
         unsafe { msg_send![self, setBadge:badge] }
     }
+
+    /// Shows or hides the checkmark next to the item
+    pub fn set_checked(&self, checked: bool) {
+        let state: NSInteger = match checked {
+            true => 1,  // NSControlStateValueOn
+            false => 0, // NSControlStateValueOff
+        };
+
+        unsafe { msg_send![self, setState:state] }
+    }
+
+    /// Sets a callback which is run every time before the item is shown, useful for keeping it up to date
+    pub fn set_on_validate(&self, on_validate: Box<dyn Fn(&Self)>) {
+        *self.ivars().on_validate.borrow_mut() = Some(on_validate);
+    }
 }
 
 extern_class!(
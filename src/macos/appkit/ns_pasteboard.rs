@@ -3,6 +3,8 @@ use objc2_foundation::{NSInteger, NSObject, NSString};
 
 use crate::macos::utils::nsstring_to_string;
 
+use super::NSData;
+
 #[allow(dead_code)]
 pub enum NSPasteboardType {
     Url,
@@ -76,34 +78,44 @@ impl NSPasteboard {
         unsafe { msg_send![self, changeCount] }
     }
 
-    pub fn get_text(&self) -> Option<String> {
-        // This function leaks memory, I have tried so many different things to no avail. I know it is not a problem
-        // with NSPasteboard because similar code in Swift is not leaky.
+    /// The types of the pasteboard's current contents, in order of preference by the app which wrote them
+    pub fn get_types(&self) -> Vec<String> {
+        let types: Option<Retained<NSObject>> = unsafe { msg_send_id![self, types] };
 
-        let contents: *mut NSString = unsafe {
-            msg_send![self, stringForType: &*NSString::from_str(&pasteboard_type_to_string(&NSPasteboardType::String))]
+        let Some(types) = types else {
+            return Vec::new();
         };
 
-        if contents.is_null() {
-            return None;
-        }
+        let count: usize = unsafe { msg_send![&*types, count] };
 
-        let contents_str = nsstring_to_string(contents);
+        (0..count)
+            .filter_map(|index| {
+                let pasteboard_type: *mut NSString =
+                    unsafe { msg_send![&*types, objectAtIndex: index] };
+                nsstring_to_string(pasteboard_type)
+            })
+            .collect()
+    }
 
-        let _: () = unsafe { msg_send![contents, dealloc] };
+    pub fn get_data(&self, pasteboard_type: &str) -> Option<Vec<u8>> {
+        let data: Option<Retained<NSData>> =
+            unsafe { msg_send_id![self, dataForType: &*NSString::from_str(pasteboard_type)] };
 
-        contents_str
+        data.map(|data| data.to_vec())
     }
 
-    pub fn set_text(&self, contents: &str) {
+    /// Replaces the pasteboard's contents with the given types & their data, nothing given clears the pasteboard
+    pub fn set_contents(&self, contents: &[(&str, &[u8])]) {
         unsafe {
             let _: NSInteger = msg_send![self, clearContents];
 
-            let _: bool = msg_send![
-                self,
-                setString:&*NSString::from_str(contents),
-                forType:&*NSString::from_str(&pasteboard_type_to_string(&NSPasteboardType::String)),
-            ];
+            for (pasteboard_type, data) in contents {
+                let _: bool = msg_send![
+                    self,
+                    setData:&*NSData::init_with_bytes(data),
+                    forType:&*NSString::from_str(pasteboard_type),
+                ];
+            }
         }
     }
 }
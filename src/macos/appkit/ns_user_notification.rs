@@ -0,0 +1,53 @@
+use objc2::{class, extern_class, msg_send, msg_send_id, mutability, rc::Retained, ClassType};
+use objc2_foundation::{NSObject, NSString};
+
+extern_class!(
+    #[derive(Debug)]
+    pub struct NSUserNotification;
+
+    unsafe impl ClassType for NSUserNotification {
+        type Super = NSObject;
+        type Mutability = mutability::InteriorMutable;
+    }
+);
+
+impl NSUserNotification {
+    pub fn init_with_text(title: &str, informative_text: &str) -> Retained<Self> {
+        let notification: Retained<Self> = unsafe { msg_send_id![Self::alloc(), init] };
+
+        unsafe {
+            let _: () = msg_send![&notification, setTitle:&*NSString::from_str(title)];
+            let _: () = msg_send![
+                &notification,
+                setInformativeText:&*NSString::from_str(informative_text)
+            ];
+        }
+
+        notification
+    }
+}
+
+extern_class!(
+    #[derive(Debug)]
+    pub struct NSUserNotificationCenter;
+
+    unsafe impl ClassType for NSUserNotificationCenter {
+        type Super = NSObject;
+        type Mutability = mutability::InteriorMutable;
+    }
+);
+
+impl NSUserNotificationCenter {
+    pub fn get_default_center() -> Retained<Self> {
+        unsafe {
+            msg_send_id![
+                class!(NSUserNotificationCenter),
+                defaultUserNotificationCenter
+            ]
+        }
+    }
+
+    pub fn deliver(&self, notification: &NSUserNotification) {
+        unsafe { msg_send![self, deliverNotification:notification] }
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::appkit::NSFileManager;
@@ -14,12 +15,18 @@ pub fn get_home_directory() -> PathBuf {
     )
 }
 
+/// Directory containing the machine-wide config managed by administrators
+pub fn get_system_config_directory() -> PathBuf {
+    PathBuf::from("/Library/Application Support/clipboard_cleanse")
+}
+
 fn config_default_poll_interval_ms() -> u64 {
     500_u64
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Config {
+    /// How often the pasteboard is checked for changes, in milliseconds
     #[serde(default = "config_default_poll_interval_ms")]
     pub poll_interval_ms: u64,
 }
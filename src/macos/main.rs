@@ -1,19 +1,101 @@
-use std::{sync::Arc, thread, time::Duration};
+use std::{thread, time::Duration};
 
 use objc2_foundation::MainThreadMarker;
 
 use crate::{
-    log,
+    app_core::{AppCore, SharedAppCore},
+    crash, ipc, log,
     macos::{app_delegate::AppDelegate, appkit::NSApplication, ui},
-    sanitization::sanitize,
+    payload::{ClipboardPayload, HTML_FORMAT, RTF_FORMAT, TEXT_FORMAT, URI_LIST_FORMAT},
+    watcher::ClipboardBackend,
 };
 
-use super::appkit::NSPasteboard;
+use super::appkit::{
+    pasteboard_type_to_string, NSPasteboard, NSPasteboardType, NSUserNotification,
+    NSUserNotificationCenter,
+};
 use crate::Config;
 
-fn poll_and_sanitize_clipboard(config: Arc<Config>) {
+/// Pasteboard types which are converted from & to payload formats, other types are kept under their own name
+const PASTEBOARD_FORMATS: [(NSPasteboardType, &str); 4] = [
+    (NSPasteboardType::String, TEXT_FORMAT),
+    (NSPasteboardType::Html, HTML_FORMAT),
+    (NSPasteboardType::Rtf, RTF_FORMAT),
+    (NSPasteboardType::Url, URI_LIST_FORMAT),
+];
+
+/// Types which the pasteboard derives from the UTF-8 text, or which embed HTML in a form that can't be sanitized, so
+/// they're left out and apps fall back to the sanitized types
+const DERIVED_PASTEBOARD_TYPES: [&str; 3] = [
+    "public.utf16-plain-text",
+    "public.utf16-external-plain-text",
+    "com.apple.webarchive",
+];
+
+/// Whether the type is a uniform type identifier, as opposed to a legacy alias (like "NSStringPboardType") or a
+/// dynamic type the pasteboard provides for another type
+fn is_own_pasteboard_type(pasteboard_type: &str) -> bool {
+    pasteboard_type.contains('.')
+        && !pasteboard_type.contains(' ')
+        && !pasteboard_type.starts_with("dyn.")
+        && !DERIVED_PASTEBOARD_TYPES.contains(&pasteboard_type)
+}
+
+impl ClipboardBackend for &NSPasteboard {
+    fn get_payload(&mut self) -> Option<ClipboardPayload> {
+        let mut payload = ClipboardPayload::default();
+
+        for pasteboard_type in self.get_types() {
+            if !is_own_pasteboard_type(&pasteboard_type) {
+                continue;
+            }
+
+            let Some(data) = self.get_data(&pasteboard_type) else {
+                continue;
+            };
+
+            let format = PASTEBOARD_FORMATS
+                .iter()
+                .find(|(known_type, _)| pasteboard_type_to_string(known_type) == pasteboard_type)
+                .map(|(_, format)| format.to_string())
+                .unwrap_or(pasteboard_type);
+
+            payload.push(&format, data);
+        }
+
+        Some(payload)
+    }
+
+    fn set_payload(&mut self, payload: &ClipboardPayload) -> Result<(), String> {
+        let types: Vec<String> = payload
+            .representations
+            .iter()
+            .map(|representation| {
+                PASTEBOARD_FORMATS
+                    .iter()
+                    .find(|(_, format)| *format == representation.format)
+                    .map(|(known_type, _)| pasteboard_type_to_string(known_type))
+                    .unwrap_or_else(|| representation.format.clone())
+            })
+            .collect();
+
+        let contents: Vec<(&str, &[u8])> = types
+            .iter()
+            .zip(&payload.representations)
+            .map(|(pasteboard_type, representation)| {
+                (pasteboard_type.as_str(), representation.data.as_slice())
+            })
+            .collect();
+
+        NSPasteboard::set_contents(self, &contents);
+        Ok(())
+    }
+}
+
+fn poll_and_sanitize_clipboard(core: SharedAppCore) {
     thread::spawn(move || {
-        let sleep_duration = Duration::from_millis(config.macos.poll_interval_ms);
+        let sleep_duration =
+            Duration::from_millis(core.lock().unwrap().config.macos.poll_interval_ms);
 
         let mut last_change_count = -1_isize;
 
@@ -22,26 +104,17 @@ fn poll_and_sanitize_clipboard(config: Arc<Config>) {
         loop {
             thread::sleep(sleep_duration);
 
+            let mut core = core.lock().unwrap();
+
+            core.tick(&mut &*pasteboard);
+
             let change_count = pasteboard.get_change_count();
             if last_change_count == change_count {
                 continue;
             }
             last_change_count = change_count;
 
-            let contents = pasteboard.get_text();
-            if contents.is_none() {
-                continue;
-            }
-            let contents = contents.unwrap();
-
-            let sanitized_contents = sanitize(&contents);
-
-            if contents != sanitized_contents {
-                pasteboard.set_text(&sanitized_contents);
-                last_change_count += 1;
-
-                log!(Info, "Sanitized copied text!");
-            }
+            core.handle_clipboard_changed(&mut &*pasteboard);
         }
     });
 }
@@ -49,22 +122,34 @@ fn poll_and_sanitize_clipboard(config: Arc<Config>) {
 pub fn main(config: Config) {
     log!(Debug, "Initializing app...");
 
-    let config = Arc::new(config);
+    let core = AppCore::new(config).shared();
+    let previous_crash = crash::report_previous_crash();
+
+    ipc::start_server(core.clone());
 
     let mtm = MainThreadMarker::new().unwrap();
     let app = NSApplication::get_shared();
 
     app.set_delegate(&AppDelegate::new(mtm, {
-        let config = config.clone();
+        let core = core.clone();
 
         Box::new(move || {
             log!(Info, "Application launched!");
-            poll_and_sanitize_clipboard(config.clone());
+            poll_and_sanitize_clipboard(core.clone());
+
+            if let Some(report_path) = &previous_crash {
+                NSUserNotificationCenter::get_default_center().deliver(
+                    &NSUserNotification::init_with_text(
+                        crash::CRASH_NOTIFICATION_TITLE,
+                        &crash::crash_notification_text(report_path),
+                    ),
+                );
+            }
         })
     }));
 
     let status_bar_item = ui::setup_status_bar_item();
-    let _status_bar_item_menu = ui::setup_menu(&mtm, &status_bar_item, config.clone());
+    let _status_bar_item_menu = ui::setup_menu(&mtm, &status_bar_item, core);
 
     log!(Debug, "Running app...");
 
@@ -6,5 +6,5 @@ mod service_management;
 pub mod ui;
 mod utils;
 
-pub use config::{get_home_directory, Config};
+pub use config::{get_home_directory, get_system_config_directory, Config};
 pub use main::main;
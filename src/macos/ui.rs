@@ -1,9 +1,13 @@
-use std::{process, sync::Arc, thread, time::Duration};
+use std::{process, thread, time::Duration};
 
 use objc2::rc::Retained;
 use objc2_foundation::MainThreadMarker;
 
-use crate::{log, macos::appkit::NSMenuItemBadge, Config};
+use crate::{
+    app_core::{pause_menu_label, SharedAppCore, PAUSE_MENU_MINUTES},
+    log,
+    macos::appkit::NSMenuItemBadge,
+};
 
 use super::{
     appkit::{
@@ -130,10 +134,116 @@ pub fn setup_toggle_login_item_menu_item(mtm: &MainThreadMarker, menu: &Retained
     menu.add_item(&menu_item);
 }
 
+pub fn setup_pause_menu_items(
+    mtm: &MainThreadMarker,
+    menu: &Retained<NSMenu>,
+    core: SharedAppCore,
+) {
+    menu.add_item(&NSMenuItem::init_section_header("Sanitizing"));
+
+    let paused_menu_item = NSMenuItem::init_with_action(
+        mtm,
+        "Paused",
+        {
+            let core = core.clone();
+
+            Box::new(move |_| {
+                let mut core = core.lock().unwrap();
+
+                match core.is_paused() {
+                    true => core.resume(),
+                    false => {
+                        if let Err(err) = core.pause(None) {
+                            log!(Error, "Failed to pause: {err}");
+                        }
+                    }
+                }
+            })
+        },
+        "",
+    );
+
+    paused_menu_item.set_on_validate({
+        let core = core.clone();
+
+        Box::new(move |this| this.set_checked(core.lock().unwrap().is_paused()))
+    });
+
+    menu.add_item(&paused_menu_item);
+
+    for minutes in PAUSE_MENU_MINUTES {
+        menu.add_item(&NSMenuItem::init_with_action(
+            mtm,
+            &pause_menu_label(minutes),
+            {
+                let core = core.clone();
+
+                Box::new(move |_| {
+                    let result = core
+                        .lock()
+                        .unwrap()
+                        .pause(Some(Duration::from_secs(minutes * 60)));
+
+                    if let Err(err) = result {
+                        log!(Error, "Failed to pause: {err}");
+                    }
+                })
+            },
+            "",
+        ));
+    }
+}
+
+pub fn setup_profile_menu_items(
+    mtm: &MainThreadMarker,
+    menu: &Retained<NSMenu>,
+    core: SharedAppCore,
+) {
+    let entries = core.lock().unwrap().profile_menu_entries();
+
+    // Only the "No Profile" entry, so no profiles are configured
+    if entries.len() <= 1 {
+        return;
+    }
+
+    menu.add_item(&NSMenuItem::init_section_header("Profiles"));
+
+    for entry in entries {
+        let menu_item = NSMenuItem::init_with_action(
+            mtm,
+            &entry.label,
+            {
+                let core = core.clone();
+                let profile = entry.profile.clone();
+
+                Box::new(move |_| {
+                    let result = core.lock().unwrap().set_active_profile(profile.as_deref());
+
+                    if let Err(err) = result {
+                        log!(Error, "Failed to switch profile: {err}");
+                    }
+                })
+            },
+            "",
+        );
+
+        menu_item.set_on_validate({
+            let core = core.clone();
+            let profile = entry.profile.clone();
+
+            Box::new(move |this| {
+                this.set_checked(core.lock().unwrap().active_profile() == profile.as_deref());
+            })
+        });
+
+        menu.add_item(&menu_item);
+    }
+}
+
 pub fn setup_menu(
     mtm: &MainThreadMarker,
     status_bar_item: &Retained<NSStatusItem>,
-    config: Arc<Config>,
+    core: SharedAppCore,
 ) -> Retained<NSMenu> {
     let menu = NSMenu::init(mtm, "Clipboard Cleanse");
 
@@ -147,7 +257,7 @@ pub fn setup_menu(
         mtm,
         "Open Config File",
         {
-            let config_path = config.config_path.clone();
+            let config_path = core.lock().unwrap().config.config_path.clone();
             Box::new(move |_| {
                 log!(Debug, "Opening config file...");
 
@@ -162,6 +272,9 @@ pub fn setup_menu(
         ",",
     ));
 
+    setup_pause_menu_items(mtm, &menu, core.clone());
+    setup_profile_menu_items(mtm, &menu, core);
+
     menu.add_item(&NSMenuItem::init_with_action(
         mtm,
         "Quit",
@@ -1,5 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::{env, process};
+
 use config::load_and_ensure_config;
 
 #[cfg(target_os = "windows")]
@@ -8,29 +10,58 @@ mod windows;
 #[cfg(target_os = "macos")]
 mod macos;
 
+mod app_core;
+mod cli;
 mod config;
+mod crash;
+mod exclusions;
+mod ipc;
 mod logger;
+mod payload;
 mod sanitization;
+mod secrets;
+mod state;
+mod watcher;
 
 pub use config::Config;
 
 fn main() {
+    logger::install();
+    crash::install_panic_hook();
+
+    // MacOS may pass a process serial number when launching the bundled app
+    let args: Vec<String> = env::args()
+        .skip(1)
+        .filter(|a| !a.starts_with("-psn_"))
+        .collect();
+
+    if !args.is_empty() {
+        #[cfg(target_os = "windows")]
+        windows::attach_parent_console();
+
+        // Only warnings & errors are logged, so they don't drown out the command's output
+        logger::set_filter(logger::LogFilter::at_level(logger::LogLevel::Warning));
+        logger::set_stderr(true);
+        process::exit(cli::run(&args));
+    }
+
     let config = load_and_ensure_config();
 
-    let log_level = logger::LogLevel::try_from(config.log_level.as_str());
+    let log_filter = logger::LogFilter::try_from(config.log_level.as_str());
 
-    if let Ok(log_level) = log_level {
-        logger::set_threshold(log_level);
+    if let Ok(log_filter) = log_filter {
+        logger::set_filter(log_filter);
     } else {
-        log!(Error, "Invalid log level configured: {log_level:#?}");
+        log!(Error, "Invalid log level configured: {log_filter:#?}");
     }
 
-    logger::set_file(match &config.log_file {
-        Some(lfp) => Some(lfp.as_str()),
-        None => None,
-    });
+    let config_description = config.describe_for_log(config.log_redaction);
+    crash::set_config_description(config_description.clone());
+
+    logger::set_redaction(config.log_redaction);
+    logger::set_file(config.log_file.as_deref(), config.file_log_options());
 
-    log!(Info, "Loaded config: {config:#?}");
+    log!(Info, "Loaded config: {config_description}");
 
     #[cfg(target_os = "macos")]
     macos::main(config);
@@ -0,0 +1,216 @@
+//! Platform-independent model of the clipboard's contents, which usually holds the same content in several formats
+//! (e.g. plain text, HTML & RTF when copying from a browser). All of them have to be written back when sanitizing,
+//! otherwise the formats which weren't sanitized would be lost.
+
+use std::time::Instant;
+
+use crate::sanitization::{
+    sanitize_html_with_deadline, sanitize_rtf_with_deadline, sanitize_with_deadline,
+    SanitizationConfig, SanitizeReport, TimedOut,
+};
+
+pub const TEXT_FORMAT: &str = "text/plain";
+pub const HTML_FORMAT: &str = "text/html";
+pub const RTF_FORMAT: &str = "text/rtf";
+pub const URI_LIST_FORMAT: &str = "text/uri-list";
+
+/// Formats which are sanitized, all of them hold UTF-8 text
+const TEXT_LIKE_FORMATS: [&str; 4] = [TEXT_FORMAT, HTML_FORMAT, RTF_FORMAT, URI_LIST_FORMAT];
+
+/// The clipboard's content in a single format, which is a MIME type for the formats the app understands or a
+/// platform-specific identifier otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Representation {
+    pub format: String,
+    pub data: Vec<u8>,
+}
+
+/// The clipboard's content in all of its formats, in the order the platform listed them
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClipboardPayload {
+    pub representations: Vec<Representation>,
+}
+
+impl ClipboardPayload {
+    #[cfg(test)]
+    pub fn from_text(text: &str) -> Self {
+        let mut payload = Self::default();
+        payload.push(TEXT_FORMAT, text.as_bytes().to_vec());
+        payload
+    }
+
+    pub fn push(&mut self, format: &str, data: Vec<u8>) {
+        self.representations.push(Representation {
+            format: format.to_string(),
+            data,
+        });
+    }
+
+    pub fn get(&self, format: &str) -> Option<&[u8]> {
+        self.representations
+            .iter()
+            .find(|representation| representation.format == format)
+            .map(|representation| representation.data.as_slice())
+    }
+
+    /// The plain text representation, if there is one
+    pub fn text(&self) -> Option<String> {
+        self.get(TEXT_FORMAT)
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+    }
+
+    /// The plain text representation, or the first other text-like one if there is none (e.g. when only HTML was
+    /// copied). This is what exclusions, secret detection & the history look at.
+    pub fn primary_text(&self) -> Option<String> {
+        self.text().or_else(|| {
+            self.representations
+                .iter()
+                .find(|representation| TEXT_LIKE_FORMATS.contains(&representation.format.as_str()))
+                .map(|representation| String::from_utf8_lossy(&representation.data).into_owned())
+        })
+    }
+
+    /// Total size of the representations which would be sanitized
+    pub fn text_like_len(&self) -> usize {
+        self.representations
+            .iter()
+            .filter(|representation| TEXT_LIKE_FORMATS.contains(&representation.format.as_str()))
+            .map(|representation| representation.data.len())
+            .sum()
+    }
+
+    /// Sanitizes every text-like representation, others are kept as-is. The report is the plain text's, plus the
+    /// changes only made to other representations (e.g. to the link behind a word in HTML).
+    pub fn sanitize(
+        &self,
+        config: &SanitizationConfig,
+        deadline: Option<Instant>,
+    ) -> Result<(ClipboardPayload, SanitizeReport), TimedOut> {
+        let mut sanitized = ClipboardPayload::default();
+        let mut text_report = SanitizeReport::default();
+        let mut other_changes = Vec::new();
+
+        for representation in &self.representations {
+            let format = representation.format.as_str();
+
+            let text = match std::str::from_utf8(&representation.data) {
+                Ok(text) if TEXT_LIKE_FORMATS.contains(&format) => text,
+                _ => {
+                    sanitized.representations.push(representation.clone());
+                    continue;
+                }
+            };
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(TimedOut);
+            }
+
+            let (sanitized_text, report) = match format {
+                HTML_FORMAT => sanitize_html_with_deadline(text, config, deadline)?,
+                RTF_FORMAT => sanitize_rtf_with_deadline(text, config, deadline)?,
+                _ => sanitize_with_deadline(text, config, deadline)?,
+            };
+
+            match format {
+                TEXT_FORMAT => text_report = report,
+                _ => other_changes.extend(report.changes),
+            }
+
+            sanitized.push(format, sanitized_text.into_bytes());
+        }
+
+        for change in other_changes {
+            if !text_report.changes.contains(&change) {
+                text_report.changes.push(change);
+            }
+        }
+
+        Ok((sanitized, text_report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_all_formats() {
+        let mut payload = ClipboardPayload::default();
+        payload.push(
+            TEXT_FORMAT,
+            b"Rick https://youtu.be/dQw4w9WgXcQ?si=abc".to_vec(),
+        );
+        payload.push(
+            HTML_FORMAT,
+            br#"<a href="https://youtu.be/dQw4w9WgXcQ?si=abc">Rick</a>"#.to_vec(),
+        );
+        payload.push(
+            RTF_FORMAT,
+            br#"{\rtf1 {\field{\*\fldinst{HYPERLINK "https://youtu.be/dQw4w9WgXcQ?si=abc"}}{\fldrslt Rick}}}"#
+                .to_vec(),
+        );
+        payload.push("image/png", b"\x89PNG?si=abc".to_vec());
+        payload.push(HTML_FORMAT, vec![0xff, 0xfe]);
+
+        let (sanitized, report) = payload
+            .sanitize(&SanitizationConfig::default(), None)
+            .unwrap();
+
+        let formats: Vec<&str> = sanitized
+            .representations
+            .iter()
+            .map(|representation| representation.format.as_str())
+            .collect();
+        assert_eq!(
+            formats,
+            vec![
+                TEXT_FORMAT,
+                HTML_FORMAT,
+                RTF_FORMAT,
+                "image/png",
+                HTML_FORMAT
+            ]
+        );
+
+        assert_eq!(
+            sanitized.text().as_deref(),
+            Some("Rick https://youtu.be/dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            sanitized.get(HTML_FORMAT),
+            Some(br#"<a href="https://youtu.be/dQw4w9WgXcQ">Rick</a>"#.as_slice())
+        );
+        assert_eq!(
+            sanitized.get(RTF_FORMAT),
+            Some(
+                br#"{\rtf1 {\field{\*\fldinst{HYPERLINK "https://youtu.be/dQw4w9WgXcQ"}}{\fldrslt Rick}}}"#
+                    .as_slice()
+            )
+        );
+        assert_eq!(sanitized.representations[3], payload.representations[3]);
+        assert_eq!(sanitized.representations[4], payload.representations[4]);
+
+        // The same link in every format is reported once
+        assert_eq!(report.changes.len(), 1);
+    }
+
+    #[test]
+    fn test_report_includes_changes_to_other_formats() {
+        let mut payload = ClipboardPayload::from_text("Rick");
+        payload.push(
+            HTML_FORMAT,
+            br#"<a href="https://youtu.be/dQw4w9WgXcQ?si=abc">Rick</a>"#.to_vec(),
+        );
+
+        let (sanitized, report) = payload
+            .sanitize(&SanitizationConfig::default(), None)
+            .unwrap();
+
+        assert_eq!(sanitized.text().as_deref(), Some("Rick"));
+        assert_ne!(sanitized, payload);
+        assert_eq!(
+            report.describe(crate::logger::LogRedaction::Strict),
+            "youtu.be (removed si)"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use url::Url;
+
+use crate::log;
+
+/// Matches text against a pattern where `*` matches any (possibly empty) sequence of characters
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` consume one more character
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Converts a host pattern to the lowercase punycode form [`Url::host_str`] returns, so e.g. `*.bücher.de` matches
+/// `www.xn--bcher-kva.de`
+pub fn normalize_host_pattern(pattern: &str) -> String {
+    idna::domain_to_ascii(pattern).unwrap_or_else(|_| pattern.to_lowercase())
+}
+
+/// Normalizes host patterns with [`normalize_host_pattern`] and URL prefixes by parsing them, ignoring invalid
+/// prefixes
+pub fn normalize_allowlist(allowlist: &[String]) -> Vec<String> {
+    allowlist
+        .iter()
+        .filter_map(|entry| match entry.contains("://") {
+            true => match Url::parse(entry) {
+                Ok(prefix) => Some(prefix.into()),
+                Err(err) => {
+                    log!(Error, "Ignoring invalid allowlist URL {entry:?}: {err}");
+                    None
+                }
+            },
+            false => Some(normalize_host_pattern(entry)),
+        })
+        .collect()
+}
+
+/// Matches text against a prefix pattern which must end at a boundary, i.e. the text either matches the pattern
+/// entirely or continues with the separator after it
+fn prefix_match(pattern: &str, text: &str, separator: char) -> bool {
+    wildcard_match(pattern, text)
+        || pattern.ends_with(separator) && wildcard_match(&format!("{pattern}*"), text)
+        || wildcard_match(&format!("{pattern}{separator}*"), text)
+}
+
+/// Whether the URL starts with the prefix, where the scheme, host and port have to be equal, the path has to start
+/// with the prefix's path segments and the query (if the prefix has one) with the prefix's parameters
+fn matches_url_prefix(prefix: &Url, url: &Url) -> bool {
+    prefix.scheme() == url.scheme()
+        && wildcard_match(
+            prefix.host_str().unwrap_or(""),
+            url.host_str().unwrap_or(""),
+        )
+        && prefix.port_or_known_default() == url.port_or_known_default()
+        && prefix_match(prefix.path(), url.path(), '/')
+        && prefix
+            .query()
+            .is_none_or(|query| prefix_match(query, url.query().unwrap_or(""), '&'))
+}
+
+/// Whether the URL matches an allowlist entry (normalized by [`normalize_allowlist`]), which is either a host pattern
+/// (e.g. `*.corp.example`) or a URL prefix (e.g. `https://example.com/campaigns/`), both supporting `*` wildcards
+pub fn is_allowlisted(allowlist: &[String], url: &Url) -> bool {
+    let host = url.host_str().unwrap_or("");
+
+    allowlist.iter().any(|entry| match entry.contains("://") {
+        true => Url::parse(entry).is_ok_and(|prefix| matches_url_prefix(&prefix, url)),
+        false => wildcard_match(entry, host),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        for (pattern, text, expected) in [
+            ("*.corp.example", "wiki.corp.example", true),
+            ("*.corp.example", "a.b.corp.example", true),
+            ("*.corp.example", "corp.example", false),
+            ("*.corp.example", "wiki.corp.example.evil.com", false),
+            ("corp.example", "corp.example", true),
+            ("shop-*.example.com", "shop-eu.example.com", true),
+            ("*", "anything", true),
+            ("a*b*c", "aXXbYYc", true),
+            ("a*b*c", "aXXbYY", false),
+        ] {
+            assert_eq!(
+                wildcard_match(pattern, text),
+                expected,
+                "{pattern:?} against {text:?}"
+            );
+        }
+    }
+}
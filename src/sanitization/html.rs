@@ -0,0 +1,363 @@
+//! Sanitizes links in HTML fragments like the ones browsers put on the clipboard. Only `href`/`src` attribute values
+//! and text which actually change are rewritten, all other markup is preserved byte-for-byte.
+
+use std::time::Instant;
+
+use super::{sanitized_url_ranges, SanitizationConfig, SanitizeReport, TimedOut};
+
+/// Attributes whose values are URLs
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// Elements whose contents are neither markup nor visible text
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// The character reference like `&lt;` or `&#39;` the `&` at the start of the text starts, if any
+fn character_reference(text: &str) -> Option<&str> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+
+    let is_reference = match name.strip_prefix('#') {
+        Some(number) => match number.strip_prefix(['x', 'X']) {
+            Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        },
+        None => {
+            name.starts_with(|c: char| c.is_ascii_alphabetic())
+                && name.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+    };
+
+    is_reference.then_some(&text[..=end])
+}
+
+/// Escapes the ampersands of a sanitized URL, except the ones starting a character reference which was already in
+/// the raw URL
+fn escape_ampersands(text: &str, raw_url: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '&' if !character_reference(&text[index..]).is_some_and(|r| raw_url.contains(r)) => {
+                escaped.push_str("&amp;")
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Sanitizes escaped text, returning `None` if nothing changed. Only `&amp;` is unescaped since it's the only
+/// character reference commonly found in URLs. Only the sanitized URLs are escaped again, everything else is copied
+/// from the raw text so e.g. an escaped `&amp;lt;` isn't turned into a `&lt;`.
+fn sanitize_escaped(
+    raw: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+    report: &mut SanitizeReport,
+) -> Result<Option<String>, TimedOut> {
+    let unescaped = raw.replace("&amp;", "&");
+    let (replacements, text_report) = sanitized_url_ranges(&unescaped, config, deadline)?;
+
+    if replacements.is_empty() {
+        return Ok(None);
+    }
+
+    // Indices of the unescaped text where an `&amp;` was shortened to `&`
+    let unescaped_ampersands: Vec<usize> = unescaped
+        .match_indices('&')
+        .map(|(index, _)| index)
+        .zip(raw.match_indices('&').map(|(index, _)| index))
+        .filter(|(_, raw_index)| raw[*raw_index..].starts_with("&amp;"))
+        .map(|(index, _)| index)
+        .collect();
+    let raw_index = |index: usize| {
+        index + 4 * unescaped_ampersands.partition_point(|ampersand| *ampersand < index)
+    };
+
+    let mut output = String::with_capacity(raw.len());
+    let mut copied_up_to = 0;
+
+    for (range, sanitized) in replacements {
+        let raw_range = raw_index(range.start)..raw_index(range.end);
+
+        output.push_str(&raw[copied_up_to..raw_range.start]);
+        output.push_str(&escape_ampersands(&sanitized, &raw[raw_range.clone()]));
+        copied_up_to = raw_range.end;
+    }
+
+    output.push_str(&raw[copied_up_to..]);
+
+    report.changes.extend(text_report.changes);
+
+    Ok(Some(output))
+}
+
+fn push_text(
+    output: &mut String,
+    text: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+    report: &mut SanitizeReport,
+) -> Result<(), TimedOut> {
+    match sanitize_escaped(text, config, deadline, report)? {
+        Some(sanitized) => output.push_str(&sanitized),
+        None => output.push_str(text),
+    }
+
+    Ok(())
+}
+
+/// Copies the start tag at `start` to the output while sanitizing its URL attributes, returning the tag's name and
+/// the index after it
+fn process_start_tag(
+    html: &str,
+    start: usize,
+    output: &mut String,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+    report: &mut SanitizeReport,
+) -> Result<(String, usize), TimedOut> {
+    let bytes = html.as_bytes();
+    let is_name_end = |b: u8| b.is_ascii_whitespace() || b == b'/' || b == b'>';
+    let skip_whitespace = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = start + 1;
+    while i < bytes.len() && !is_name_end(bytes[i]) {
+        i += 1;
+    }
+    let tag_name = html[start + 1..i].to_ascii_lowercase();
+
+    let mut copied_up_to = start;
+
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+
+        if i >= bytes.len() || bytes[i] == b'>' {
+            break;
+        }
+
+        let name_start = i;
+        while i < bytes.len() && !is_name_end(bytes[i]) && bytes[i] != b'=' {
+            i += 1;
+        }
+        let name = html[name_start..i].to_ascii_lowercase();
+
+        i = skip_whitespace(i);
+        if i >= bytes.len() || bytes[i] != b'=' {
+            continue;
+        }
+        i = skip_whitespace(i + 1);
+
+        let (value_start, value_end) = match bytes.get(i) {
+            Some(quote @ (b'"' | b'\'')) => {
+                let value_start = i + 1;
+                let value_end = html[value_start..]
+                    .find(*quote as char)
+                    .map(|offset| value_start + offset)
+                    .unwrap_or(html.len());
+
+                i = (value_end + 1).min(html.len());
+                (value_start, value_end)
+            }
+            _ => {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+
+                (value_start, i)
+            }
+        };
+
+        if !URL_ATTRIBUTES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let value = &html[value_start..value_end];
+
+        if let Some(sanitized) = sanitize_escaped(value, config, deadline, report)? {
+            output.push_str(&html[copied_up_to..value_start]);
+            output.push_str(&sanitized);
+            copied_up_to = value_end;
+        }
+    }
+
+    let end = (i + 1).min(html.len());
+    output.push_str(&html[copied_up_to..end]);
+
+    Ok((tag_name, end))
+}
+
+/// Sanitizes the URLs in an HTML fragment's link attributes & text, also returning a report of what was removed
+pub fn sanitize_html(html: &str, config: &SanitizationConfig) -> (String, SanitizeReport) {
+    sanitize_html_with_deadline(html, config, None)
+        .expect("Sanitizing without a deadline can't time out")
+}
+
+/// [`sanitize_html`], giving up once the deadline (if any) passed
+pub fn sanitize_html_with_deadline(
+    html: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+) -> Result<(String, SanitizeReport), TimedOut> {
+    let mut output = String::with_capacity(html.len());
+    let mut report = SanitizeReport::default();
+
+    let mut text_start = 0;
+    let mut search_from = 0;
+
+    while let Some(offset) = html[search_from..].find('<') {
+        let start = search_from + offset;
+        let after = &html[start + 1..];
+        let starts_with_letter = |s: &str| s.starts_with(|c: char| c.is_ascii_alphabetic());
+
+        let is_comment = after.starts_with("!--");
+        let is_other_markup = after.starts_with(['!', '?'])
+            || (after.starts_with('/') && starts_with_letter(&after[1..]));
+        let is_start_tag = starts_with_letter(after);
+
+        if !is_comment && !is_other_markup && !is_start_tag {
+            // A lone `<` in text
+            search_from = start + 1;
+            continue;
+        }
+
+        push_text(
+            &mut output,
+            &html[text_start..start],
+            config,
+            deadline,
+            &mut report,
+        )?;
+
+        let end = if is_comment {
+            let end = html[start..]
+                .find("-->")
+                .map(|offset| start + offset + 3)
+                .unwrap_or(html.len());
+
+            output.push_str(&html[start..end]);
+            end
+        } else if is_other_markup {
+            let end = html[start..]
+                .find('>')
+                .map(|offset| start + offset + 1)
+                .unwrap_or(html.len());
+
+            output.push_str(&html[start..end]);
+            end
+        } else {
+            let (tag_name, end) =
+                process_start_tag(html, start, &mut output, config, deadline, &mut report)?;
+
+            if RAW_TEXT_ELEMENTS.contains(&tag_name.as_str()) {
+                // Raw text is copied as-is, its end tag is handled like any other
+                let raw_text_end = html[end..]
+                    .to_ascii_lowercase()
+                    .find(&format!("</{tag_name}"))
+                    .map(|offset| end + offset)
+                    .unwrap_or(html.len());
+
+                output.push_str(&html[end..raw_text_end]);
+                raw_text_end
+            } else {
+                end
+            }
+        };
+
+        text_start = end;
+        search_from = end;
+    }
+
+    push_text(
+        &mut output,
+        &html[text_start..],
+        config,
+        deadline,
+        &mut report,
+    )?;
+
+    Ok((output, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        sanitize_html(html, &SanitizationConfig::default()).0
+    }
+
+    #[test]
+    fn test_sanitizes_links() {
+        for (case, expected) in [
+            (
+                r#"<a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;si=abc" class="link">https://youtu.be/dQw4w9WgXcQ?si=abc</a>"#,
+                r#"<a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ" class="link">https://youtu.be/dQw4w9WgXcQ</a>"#,
+            ),
+            (
+                "<A HREF='https://example.com/a?utm_source=x&amp;id=1&amp;utm_medium=y'>docs</A>",
+                "<A HREF='https://example.com/a?id=1'>docs</A>",
+            ),
+            (
+                r#"<img alt="" src=https://example.com/a.png?utm_source=x width=5>"#,
+                r#"<img alt="" src=https://example.com/a.png width=5>"#,
+            ),
+            (
+                "Tom &amp; Jerry &lt;3 https://example.com/?utm_source=x&amp;id=1 &copy;",
+                "Tom &amp; Jerry &lt;3 https://example.com/?id=1 &copy;",
+            ),
+            // Escaped character references stay escaped next to a sanitized URL
+            (
+                "<p>Write &amp;lt; as &lt; and &amp;copy; https://example.com/?utm_source=x&amp;id=1&amp;lt=2</p>",
+                "<p>Write &amp;lt; as &lt; and &amp;copy; https://example.com/?id=1&amp;lt=2</p>",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_preserves_other_markup() {
+        for case in [
+            "<html>\r\n<body>\r\n<!--StartFragment--><p style='color: red'>Hi <b>there</b> 1 < 2</p><!--EndFragment-->\r\n</body>\r\n</html>",
+            r#"<a href="https://example.com/?id=1&amp;page=2" data-x='https://youtu.be/dQw4w9WgXcQ?si=abc'>link</a>"#,
+            "<!-- https://youtu.be/dQw4w9WgXcQ?si=abc --><!DOCTYPE html><?xml version=\"1.0\"?>",
+            "<script>var u = \"https://youtu.be/dQw4w9WgXcQ?si=abc\";</script><STYLE>a[href*='?si=1'] {}</STYLE>",
+            "<p unterminated=\"https://youtu.be/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(sanitize(case), case);
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let (_, report) = sanitize_html(
+            r#"<a href="https://youtu.be/dQw4w9WgXcQ?si=abc">https://open.spotify.com/track/1?si=def</a>"#,
+            &SanitizationConfig::default(),
+        );
+
+        let hosts: Vec<&str> = report.changes.iter().map(|c| c.host.as_str()).collect();
+        assert_eq!(hosts, vec!["youtu.be", "open.spotify.com"]);
+    }
+
+    #[test]
+    fn test_deadline() {
+        assert_eq!(
+            sanitize_html_with_deadline(
+                r#"<a href="https://youtu.be/dQw4w9WgXcQ?si=abc">Rick</a>"#,
+                &SanitizationConfig::default(),
+                Some(Instant::now())
+            ),
+            Err(TimedOut)
+        );
+    }
+}
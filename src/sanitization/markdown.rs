@@ -0,0 +1,110 @@
+//! Finds the link destinations in Markdown, so URLs in them end where the Markdown syntax says they do instead of
+//! wherever the URL pattern stops, which would e.g. swallow the closing parenthesis of an inline link.
+
+use std::{ops::Range, sync::LazyLock};
+
+use regex::Regex;
+
+/// Start of a reference definition like `[label]: https://...`, up to the destination
+static REFERENCE_DEFINITION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^ {0,3}\[[^\]\n]+\]:[ \t]*").unwrap());
+
+/// An autolink like `<https://...>`
+static AUTOLINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<([a-zA-Z][a-zA-Z0-9+.\-]{1,31}:[^\s<>]*)>").unwrap());
+
+/// Parses the link destination starting at `start`, returning its range without enclosing angle brackets
+fn destination(text: &str, start: usize) -> Option<Range<usize>> {
+    let rest = &text[start..];
+
+    if let Some(enclosed) = rest.strip_prefix('<') {
+        let end = enclosed.find(['>', '<', '\n'])?;
+
+        return enclosed[end..]
+            .starts_with('>')
+            .then_some(start + 1..start + 1 + end);
+    }
+
+    // Unenclosed destinations may contain balanced (or escaped) parentheses
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut end = rest.len();
+
+    for (index, c) in rest.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = index;
+                break;
+            }
+            ')' => depth -= 1,
+            c if c.is_whitespace() || c.is_control() => {
+                end = index;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    (end > 0).then_some(start..start + end)
+}
+
+/// Ranges of the destinations of inline links & images, reference definitions and autolinks, ordered by their start
+pub fn link_destinations(text: &str) -> Vec<Range<usize>> {
+    let inline = text
+        .match_indices("](")
+        .filter_map(|(index, _)| destination(text, index + 2));
+
+    let references = REFERENCE_DEFINITION_REGEX
+        .find_iter(text)
+        .filter_map(|matched| destination(text, matched.end()));
+
+    let autolinks = AUTOLINK_REGEX
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1))
+        .map(|matched| matched.range());
+
+    let mut destinations: Vec<Range<usize>> = inline.chain(references).chain(autolinks).collect();
+    destinations.sort_by_key(|destination| destination.start);
+    // An enclosed reference definition destination is an autolink as well
+    destinations.dedup();
+
+    destinations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_destinations() {
+        for (text, expected) in [
+            ("[a](https://a.com)", vec!["https://a.com"]),
+            ("[a](https://a.com/b_(c)) d", vec!["https://a.com/b_(c)"]),
+            (r"[a](https://a.com/b\)) d", vec![r"https://a.com/b\)"]),
+            (
+                r#"![a](https://a.com/b.png "Title")"#,
+                vec!["https://a.com/b.png"],
+            ),
+            ("[a](<https://a.com/b c>)", vec!["https://a.com/b c"]),
+            (
+                "[a]: https://a.com 'Title'\n  [b]: <https://b.com>",
+                vec!["https://a.com", "https://b.com"],
+            ),
+            (
+                "see <https://a.com/?b=c> and [a]()",
+                vec!["https://a.com/?b=c"],
+            ),
+            ("not [a] (https://a.com)", vec![]),
+        ] {
+            let destinations: Vec<&str> = link_destinations(text)
+                .into_iter()
+                .map(|destination| &text[destination])
+                .collect();
+
+            assert_eq!(destinations, expected, "{text}");
+        }
+    }
+}
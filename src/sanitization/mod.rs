@@ -0,0 +1,1570 @@
+mod allowlist;
+mod html;
+mod markdown;
+mod nested;
+mod report;
+mod rtf;
+mod rules;
+mod scanner;
+
+use std::{collections::HashSet, hash::RandomState, ops::Range, time::Instant};
+
+use url::{form_urlencoded, Url};
+
+pub use html::{sanitize_html, sanitize_html_with_deadline};
+pub use report::{SanitizeReport, UrlChange};
+pub use rtf::{sanitize_rtf, sanitize_rtf_with_deadline};
+pub use rules::{SanitizationConfig, SanitizationProfile};
+
+use allowlist::is_allowlisted;
+use rules::{Rule, BUILTIN_RULES};
+
+/// Text is scanned in chunks of about this many bytes, so a deadline can be checked in between
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A URL's text split into its parts, so sanitizing edits the text the user copied instead of re-serializing the
+/// parsed URL, which would turn Unicode hosts into punycode and percent-encode Unicode paths
+#[derive(Debug, PartialEq)]
+struct UrlParts<'a> {
+    /// Scheme & authority
+    origin: &'a str,
+    path: &'a str,
+    /// Without the `?`
+    query: Option<&'a str>,
+    /// Including the `#`
+    fragment: &'a str,
+}
+
+impl<'a> UrlParts<'a> {
+    fn split(url: &'a str) -> Self {
+        let authority_start = url.find("://").map_or(0, |index| index + 3);
+        let path_start = url[authority_start..]
+            .find(['/', '?', '#'])
+            .map_or(url.len(), |index| authority_start + index);
+        let fragment_start = url[path_start..]
+            .find('#')
+            .map_or(url.len(), |index| path_start + index);
+
+        let (path, query) = match url[path_start..fragment_start].split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (&url[path_start..fragment_start], None),
+        };
+
+        Self {
+            origin: &url[..path_start],
+            path,
+            query,
+            fragment: &url[fragment_start..],
+        }
+    }
+}
+
+/// Which query parameters to remove from a URL
+#[derive(Debug, Default)]
+struct QueryParamFilter<'a> {
+    remove: HashSet<&'a str, RandomState>,
+    /// Never removed, takes priority over everything else
+    keep: HashSet<&'a str, RandomState>,
+    /// When set, everything not listed is removed
+    allow_only: Option<HashSet<&'a str, RandomState>>,
+}
+
+impl QueryParamFilter<'_> {
+    fn should_remove(&self, key: &str) -> bool {
+        if self.keep.contains(key) {
+            return false;
+        }
+
+        self.remove.contains(key)
+            || self
+                .allow_only
+                .as_ref()
+                .is_some_and(|allowed| !allowed.contains(key))
+    }
+}
+
+/// Removes the filtered parameters from a query string, returning the new query (`None` once nothing is left) and the
+/// removed (key, value) pairs. The parameters which are kept are left exactly as they were written.
+fn remove_query_params(
+    query: &str,
+    should_remove: impl Fn(&str) -> bool,
+) -> (Option<String>, Vec<(String, String)>) {
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+
+    for param in query.split('&') {
+        match form_urlencoded::parse(param.as_bytes()).next() {
+            Some((key, value)) if should_remove(&key) => {
+                removed.push((key.into_owned(), value.into_owned()))
+            }
+            Some(_) => kept.push(param),
+            None => {}
+        }
+    }
+
+    if removed.is_empty() {
+        return (Some(query.to_string()), removed);
+    }
+
+    ((!kept.is_empty()).then(|| kept.join("&")), removed)
+}
+
+/// Separates the fragment's directives (like text fragments) from the anchor, e.g. `#section:~:text=...`
+const FRAGMENT_DIRECTIVE: &str = ":~:";
+
+/// Removes the filtered parameters from a fragment (including its `#`), returning the new fragment (empty once
+/// nothing is left) and the removed (key, value) pairs. The query of single-page app routes is filtered like the URL's
+/// query, other parameters (including the fragment directive's) only if a rule lists them, so anchors like
+/// `#section-2` are kept.
+fn sanitize_fragment(
+    fragment: &str,
+    query_filter: &QueryParamFilter,
+    fragment_filter: &QueryParamFilter,
+    config: &SanitizationConfig,
+    depth: usize,
+    nested_changes: &mut Vec<UrlChange>,
+) -> (String, Vec<(String, String)>) {
+    let Some(fragment) = fragment.strip_prefix('#') else {
+        return (String::new(), Vec::new());
+    };
+
+    let (anchor, directive) = match fragment.split_once(FRAGMENT_DIRECTIVE) {
+        Some((anchor, directive)) => (anchor, Some(directive)),
+        None => (fragment, None),
+    };
+
+    let mut removed = Vec::new();
+
+    // Single-page apps keep their route's query in the fragment, e.g. `#/page?utm_campaign=...`
+    let mut sanitized = match anchor.split_once('?') {
+        Some((route, route_query)) => {
+            let (route_query, route_removed) =
+                remove_query_params(route_query, |key| query_filter.should_remove(key));
+            removed.extend(route_removed);
+
+            match route_query {
+                Some(route_query) => format!(
+                    "{route}?{}",
+                    sanitize_nested_urls(&route_query, config, depth, nested_changes)
+                ),
+                None => route.to_string(),
+            }
+        }
+        None => {
+            let (anchor, anchor_removed) =
+                remove_query_params(anchor, |key| fragment_filter.should_remove(key));
+            removed.extend(anchor_removed);
+
+            anchor.unwrap_or_default()
+        }
+    };
+
+    if let Some(directive) = directive {
+        let (directive, directive_removed) = remove_query_params(directive, |key| {
+            fragment_filter.should_remove(&format!("{FRAGMENT_DIRECTIVE}{key}"))
+        });
+        removed.extend(
+            directive_removed
+                .into_iter()
+                .map(|(key, value)| (format!("{FRAGMENT_DIRECTIVE}{key}"), value)),
+        );
+
+        if let Some(directive) = directive {
+            sanitized.push_str(FRAGMENT_DIRECTIVE);
+            sanitized.push_str(&directive);
+        }
+    }
+
+    // A lone `#` is only dropped if it's what was left after removing parameters
+    if sanitized.is_empty() && !removed.is_empty() {
+        return (sanitized, removed);
+    }
+
+    (format!("#{sanitized}"), removed)
+}
+
+pub fn sanitize(text: &str) -> String {
+    sanitize_with_config(text, &SanitizationConfig::default())
+}
+
+pub fn sanitize_with_config(text: &str, config: &SanitizationConfig) -> String {
+    sanitize_with_report(text, config).0
+}
+
+/// Sanitizes the URLs nested in a query string's parameter values, adding what was changed to `changes`
+fn sanitize_nested_urls(
+    query: &str,
+    config: &SanitizationConfig,
+    depth: usize,
+    changes: &mut Vec<UrlChange>,
+) -> String {
+    query
+        .split('&')
+        .map(|param| {
+            let Some((key, value)) = param.split_once('=') else {
+                return param.to_string();
+            };
+
+            match nested::sanitize_nested_url(value, config, depth) {
+                Some((value, nested_changes)) => {
+                    changes.extend(nested_changes);
+                    format!("{key}={value}")
+                }
+                None => param.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Sanitizes a single matched URL, returning the text to replace it with and what was changed (including in nested
+/// URLs), or `None` if it should be left as-is
+fn sanitize_url(
+    matched: &str,
+    url: Url,
+    config: &SanitizationConfig,
+) -> Option<(String, Vec<UrlChange>)> {
+    sanitize_url_at_depth(matched, url, config, 0)
+}
+
+/// [`sanitize_url`] for a URL nested `depth` levels deep in other URLs
+fn sanitize_url_at_depth(
+    matched: &str,
+    url: Url,
+    config: &SanitizationConfig,
+    depth: usize,
+) -> Option<(String, Vec<UrlChange>)> {
+    // Punycode for internationalized domains, IP literals are matched by rules like any other host
+    let domain = url.host_str()?.to_string();
+
+    if is_allowlisted(&config.allowlist, &url) {
+        return None;
+    }
+
+    let rules: Vec<&Rule> = BUILTIN_RULES
+        .iter()
+        .chain(config.rules.iter())
+        .filter(|r| config.categories.is_enabled(r.category) && r.applies_to(&domain))
+        .collect();
+
+    let mut query_param_filter = QueryParamFilter {
+        remove: rules
+            .iter()
+            .flat_map(|r| r.remove_params.iter().map(|p| p.as_str()))
+            .collect(),
+        ..Default::default()
+    };
+
+    for host_override in config
+        .host_overrides
+        .iter()
+        .filter(|o| o.applies_to(&domain))
+    {
+        query_param_filter
+            .keep
+            .extend(host_override.keep.iter().map(|p| p.as_str()));
+
+        if let Some(allow_only) = &host_override.allow_only {
+            query_param_filter
+                .allow_only
+                .get_or_insert_with(HashSet::new)
+                .extend(allow_only.iter().map(|p| p.as_str()));
+        }
+    }
+
+    let fragment_param_filter = QueryParamFilter {
+        remove: rules
+            .iter()
+            .flat_map(|r| r.remove_fragment_params.iter().map(|p| p.as_str()))
+            .collect(),
+        keep: query_param_filter.keep.clone(),
+        allow_only: None,
+    };
+
+    let parts = UrlParts::split(matched);
+
+    let path = rules
+        .iter()
+        .flat_map(|r| &r.path_rewrites)
+        .fold(parts.path.to_string(), |path, rewrite| rewrite.apply(&path));
+    let path_changed = path != parts.path;
+
+    // The canonical form replaces the origin & path, and may start the query (e.g. with a video id). The rest of the
+    // URL is sanitized as usual.
+    let original_base = format!("{}{path}", parts.origin);
+    let canonical = config
+        .canonicalize
+        .then(|| {
+            rules.iter().find_map(|r| {
+                let canonical_url = r.canonical.as_ref()?;
+                Some((canonical_url, canonical_url.apply(&path)?))
+            })
+        })
+        .flatten();
+    let canonicalized = canonical
+        .as_ref()
+        .is_some_and(|(_, canonical)| *canonical != original_base);
+
+    if let Some((canonical_url, _)) = &canonical {
+        query_param_filter
+            .remove
+            .extend(canonical_url.remove_params.iter().map(|p| p.as_str()));
+    }
+
+    let (base, canonical_query) = match &canonical {
+        Some((_, canonical)) => match canonical.split_once('?') {
+            Some((base, query)) => (base, Some(query)),
+            None => (canonical.as_str(), None),
+        },
+        None => (original_base.as_str(), None),
+    };
+
+    let mut nested_changes = Vec::new();
+
+    let (query, mut removed_params) = match parts.query {
+        Some(query) => remove_query_params(query, |key| query_param_filter.should_remove(key)),
+        None => (None, Vec::new()),
+    };
+    let query = query.map(|query| sanitize_nested_urls(&query, config, depth, &mut nested_changes));
+    let query = match (canonical_query, query) {
+        (Some(canonical_query), Some(query)) => Some(format!("{canonical_query}&{query}")),
+        (canonical_query, query) => query.or(canonical_query.map(str::to_string)),
+    };
+
+    let (fragment, removed) = sanitize_fragment(
+        parts.fragment,
+        &query_param_filter,
+        &fragment_param_filter,
+        config,
+        depth,
+        &mut nested_changes,
+    );
+    removed_params.extend(removed);
+
+    let mut url = base.to_string();
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(&query);
+    }
+    url.push_str(&fragment);
+
+    let change =
+        (!removed_params.is_empty() || path_changed || canonicalized).then_some(UrlChange {
+            host: domain,
+            removed_params,
+            path_changed,
+            canonicalized,
+        });
+
+    Some((url, change.into_iter().chain(nested_changes).collect()))
+}
+
+/// Splits the text into chunks of at least `size` bytes (except for the last one), splitting after whitespace so URLs
+/// aren't split. Text without whitespace for another `size` bytes (e.g. base64) is split anyway, so the deadline is
+/// still checked regularly.
+fn chunks(text: &str, size: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+
+        let mut limit = end.saturating_add(size).min(rest.len());
+        while !rest.is_char_boundary(limit) {
+            limit -= 1;
+        }
+
+        let end = match rest[end..limit]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+        {
+            Some((i, c)) => end + i + c.len_utf8(),
+            None => limit,
+        };
+
+        let chunk = &rest[..end];
+        rest = &rest[end..];
+
+        Some(chunk)
+    })
+}
+
+/// Finds the URLs in the text, a URL in a Markdown link ending where the link's destination does
+fn find_urls(text: &str) -> impl Iterator<Item = Range<usize>> {
+    let destinations = markdown::link_destinations(text);
+
+    scanner::find_urls(text).into_iter().map(move |url| {
+        let end = destinations
+            .iter()
+            .find(|destination| destination.contains(&url.start))
+            .map_or(url.end, |destination| url.end.min(destination.end));
+
+        url.start..end
+    })
+}
+
+/// Sanitizing took longer than allowed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedOut;
+
+/// Sanitizes the text, also returning a report of what was removed
+pub fn sanitize_with_report(text: &str, config: &SanitizationConfig) -> (String, SanitizeReport) {
+    sanitize_with_deadline(text, config, None)
+        .expect("Sanitizing without a deadline can't time out")
+}
+
+/// Range of text and what to replace it with
+type Replacement = (Range<usize>, String);
+
+/// Finds the URLs to replace chunk by chunk, returning the ranges of the text to replace with their sanitized form
+/// in order. Gives up once the deadline (if any) passed.
+fn sanitized_url_ranges(
+    text: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+) -> Result<(Vec<Replacement>, SanitizeReport), TimedOut> {
+    let url_parser = Url::options();
+
+    let mut replacements = Vec::new();
+    let mut report = SanitizeReport::default();
+    let mut chunk_start = 0;
+
+    for chunk in chunks(text, CHUNK_SIZE) {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(TimedOut);
+        }
+
+        for range in find_urls(chunk) {
+            let matched = &chunk[range.clone()];
+
+            let Ok(url) = url_parser.parse(matched) else {
+                continue;
+            };
+
+            let Some((sanitized, change)) = sanitize_url(matched, url, config) else {
+                continue;
+            };
+
+            replacements.push((
+                chunk_start + range.start..chunk_start + range.end,
+                sanitized,
+            ));
+            report.changes.extend(change);
+        }
+
+        chunk_start += chunk.len();
+    }
+
+    Ok((replacements, report))
+}
+
+/// Sanitizes the text chunk by chunk, giving up once the deadline (if any) passed
+pub fn sanitize_with_deadline(
+    text: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+) -> Result<(String, SanitizeReport), TimedOut> {
+    let (replacements, report) = sanitized_url_ranges(text, config, deadline)?;
+
+    let mut output = String::with_capacity(text.len());
+    let mut copied_up_to = 0;
+
+    for (range, sanitized) in replacements {
+        output.push_str(&text[copied_up_to..range.start]);
+        output.push_str(&sanitized);
+        copied_up_to = range.end;
+    }
+
+    output.push_str(&text[copied_up_to..]);
+
+    Ok((output, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rules::{Categories, HostOverride, RuleCategory};
+    use super::*;
+    use crate::logger::LogRedaction;
+
+    const LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit.";
+
+    #[test]
+    fn test_ignores_normal_stuff() {
+        for case in [
+            "",
+            "abracadabra",
+            "https://iapetus11.me/",
+            "http://iapetus11.me/",
+            "test one two three !@#$%^&*()_+[]{}|\\/~`,.<>-='\"",
+            LOREM_IPSUM,
+        ] {
+            assert_eq!(sanitize(case), case);
+        }
+    }
+
+    #[test]
+    fn test_trailing_question_mark_left_alone() {
+        assert_eq!(sanitize("https://iapetus11.me/?"), "https://iapetus11.me/?");
+
+        for (case, expected) in [
+            ("https://iapetus11.me/?", "https://iapetus11.me/?"),
+            ("https://iapetus11.me/test/?", "https://iapetus11.me/test/?"),
+            (
+                "https://iapetus11.me/?#abracdabra",
+                "https://iapetus11.me/?#abracdabra",
+            ),
+            (
+                "https://iapetus11.me/test/?#abracdabra",
+                "https://iapetus11.me/test/?#abracdabra",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_trailing_hash_left_alone() {
+        for (case, expected) in [
+            ("https://iapetus11.me/#", "https://iapetus11.me/#"),
+            ("https://iapetus11.me/test/#", "https://iapetus11.me/test/#"),
+            (
+                "https://iapetus11.me/?query=abc#",
+                "https://iapetus11.me/?query=abc#",
+            ),
+            (
+                "https://iapetus11.me/test/?query=abc#",
+                "https://iapetus11.me/test/?query=abc#",
+            ),
+            (
+                "https://iapetus11.me/test/?#",
+                "https://iapetus11.me/test/?#",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_trailing_slash_left_alone() {
+        for (case, expected) in [
+            ("https://test.example.com/", "https://test.example.com/"),
+            ("https://test.example.com", "https://test.example.com"),
+            (
+                "https://test.example.com/?test_query=123",
+                "https://test.example.com/?test_query=123",
+            ),
+            (
+                "https://test.example.com?test_query=123",
+                "https://test.example.com?test_query=123",
+            ),
+            (
+                "https://test.example.com?test_query=123&utm_campaign=vb-discord-embed",
+                "https://test.example.com?test_query=123",
+            ),
+            (
+                "https://test.example.com?utm_campaign=vb-discord-embed&test_query=123",
+                "https://test.example.com?test_query=123",
+            ),
+            (
+                "https://test.example.com/test.example.com/",
+                "https://test.example.com/test.example.com/",
+            ),
+            (
+                "https://test.example.com/test.example.com",
+                "https://test.example.com/test.example.com",
+            ),
+            (
+                "https://test.example.com/example/wow/",
+                "https://test.example.com/example/wow/",
+            ),
+            (
+                "https://test.example.com/example/wow",
+                "https://test.example.com/example/wow",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_spotify() {
+        const WITH_BS: &str =
+            "https://open.spotify.com/track/2bDJK04GaUdkBAuZoPt2ch?si=1efd90192fb242df";
+        const NO_BS: &str = "https://open.spotify.com/track/2bDJK04GaUdkBAuZoPt2ch";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_youtu_dot_be() {
+        const WITH_BS: &str = "https://youtu.be/dQw4w9WgXcQ?si=LPnCW-jopJtkzMRx";
+        const NO_BS: &str = "https://youtu.be/dQw4w9WgXcQ";
+        const WITH_BS_WITH_TIMESTAMP: &str =
+            "https://youtu.be/dQw4w9WgXcQ?si=LPnCW-jopJtkzMRx&t=47";
+        const NO_BS_WITH_TIMESTAMP: &str = "https://youtu.be/dQw4w9WgXcQ?t=47";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(WITH_BS_WITH_TIMESTAMP), NO_BS_WITH_TIMESTAMP),
+            (
+                sanitize(&format!(
+                    "{LOREM_IPSUM} {WITH_BS_WITH_TIMESTAMP} {LOREM_IPSUM}"
+                )),
+                &format!("{LOREM_IPSUM} {NO_BS_WITH_TIMESTAMP} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS_WITH_TIMESTAMP), NO_BS_WITH_TIMESTAMP),
+            (
+                sanitize(&format!(
+                    "{LOREM_IPSUM} {NO_BS_WITH_TIMESTAMP} {LOREM_IPSUM}"
+                )),
+                &format!("{LOREM_IPSUM} {NO_BS_WITH_TIMESTAMP} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_reddit() {
+        const WITH_BS: &str =
+        "https://www.reddit.com/r/ShittySysadmin/comments/1foocp8/my_boss_found_out_what_the_ai_deep_learning/?utm_source=share&utm_medium=web3x&utm_name=web3xcss&utm_term=1&utm_content=share_button";
+        const NO_BS: &str = "https://www.reddit.com/r/ShittySysadmin/comments/1foocp8/my_boss_found_out_what_the_ai_deep_learning/";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_amazon_search() {
+        const WITH_BS: &str =
+        "https://www.amazon.com/s?k=among+us&crid=3LTHOHS5L9240&sprefix=among+us%2Caps%2C182&ref=nb_sb_noss_1";
+        const NO_BS: &str = "https://www.amazon.com/s?k=among+us";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_amazon_product() {
+        const WITH_BS: &str =
+        "https://www.amazon.com/Inflatable-Costume-Halloween-Spacesuit-Astronaut/dp/B09BJS9BBJ/ref=sr_1_40?crid=2Z3A2U3LQAC8Z&dib=eyJ2IjoiMSJ9.QD-XGq5wyJMbhSkgs3XeTqbz8N9tRCkGBrM-kKlN8Xfk3jE6NS3EA_QhY5mkLjnSW1ah7_UCLuMP9zoRSZSTpa1oK1Y3iTMbZNi48GU0NLVAgUothoMaCiku4AnqBKsnUH0RP7qVzz5irWJ3YDguCNwQxM6m_XzUvKJeeEGllJVPM31VJ0t0JcUD9PaXs6MFCm1unguEF1iaLr6Cp9zg59RazntwyXblgSEiXopgshSCyc4GmumipJqTodXxh8riuHxDYQ-dtIUEZuQrtcqpsWEtrargU88sKW6iqmsXB84.msLyoWCuM8cBB-gYfdt-nZwDszc7wfcap6eUvMlqABc&dib_tag=se&keywords=among+us&qid=1728832966&sprefix=among+u%2Caps%2C115&sr=8-40";
+        const NO_BS: &str =
+            "https://www.amazon.com/Inflatable-Costume-Halloween-Spacesuit-Astronaut/dp/B09BJS9BBJ";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_google() {
+        const WITH_BS: &str =
+        "https://www.google.com/search?q=let+me+google+that+for+you&udm=14&rlz=1C5CHFA_enUS1022US1022&oq=let+me+google+that+for+you&gs_lcrp=EgZjaHJvbWUyBggAEEUYOTIGCAEQRRhAMgYIAhAAGEAyBggDEAAYQDIGCAQQABhA0gEIMjM0MWowajeoAgCwAgA&sourceid=chrome&ie=UTF-8";
+        const NO_BS: &str =
+            "https://www.google.com/search?q=let+me+google+that+for+you&udm=14&oq=let+me+google+that+for+you&ie=UTF-8";
+
+        const WITH_BS_BAND: &str = "https://www.google.com/search?sca_esv=eab902f88a49963b&rlz=1C5CHFA_enUS1022US1022&q=microwave+(band)&source=lnms&fbs=AEQNm0Aa4sjWe7Rqy32pFwRj0UkWd8nbOJfsBGGB5IQQO6L3J_86uWOeqwdnV0yaSF-x2jogM63VUdBhAMVqo6r6ESHk5gYCycVYeSiTstipcfTqmIhRyNTkvcUNlFNBFo1Ct8djYRwYkoYYVQCjXdCMx_QpPNuVSAotPifJ1VZwOnoSbLVxcdVtmtRchwzdBXA8SbTftA_onVzsK5maxZvT9OLVuyPkOw&sa=X&ved=2ahUKEwj2hOa0iIyJAxWaTDABHaNRCpAQ0pQJegQIEhAB&biw=1512&bih=793&dpr=2";
+        // Kept parameters are left as they were written rather than being re-encoded
+        const NO_BS_BAND: &str = "https://www.google.com/search?q=microwave+(band)";
+
+        const WITH_BS_MOVIE: &str = "https://www.google.com/search?sca_esv=eab902f88a49963b&rlz=1C5CHFA_enUS1022US1022&q=iron+man&source=lnms&fbs=AEQNm0D8w290mrrxEB5tt05ZGXVzey53Ax5hYPfVm2hPmC54fDCmp6uNdUTjug5J6iXC9R56JST0dqljAGrQuD17_gPphSdptBqrpONac59aEg0atuDCge5YKBwQJ8eEtI4mHmFziRkzFQkWBan59H2WVJl5UuGam0MykN5C1gPIIzI_eTXdB2q5r30l2wS3XYg4VirYrzJ_ZDXOEm__K690lE80L8L41w&sa=X&ved=2ahUKEwi38O_tiIyJAxVp5MkDHangI4gQ0pQJegQIEhAB&biw=1512&bih=793&dpr=2";
+        const NO_BS_MOVIE: &str = "https://www.google.com/search?q=iron+man";
+
+        const WITH_BS_ACTOR: &str = "https://www.google.com/search?sa=X&sca_esv=eab902f88a49963b&rlz=1C5CHFA_enUS1022US1022&q=Robert+Downey+Jr.&stick=H4sIAAAAAAAAAONgFuLUz9U3SKnKSapQAjMNzaqMsrSEspOt9NMyc3LBhFVyYnHJIlbBoPyk1KISBZf88rzUSgWvIr0drIwAiCXEPEMAAAA&ved=2ahUKEwjHp-uciYyJAxUJM9AFHb4YOl8QgOQBegQIRhAG&biw=1512&bih=793&dpr=2";
+        const NO_BS_ACTOR: &str = "https://www.google.com/search?q=Robert+Downey+Jr.&stick=H4sIAAAAAAAAAONgFuLUz9U3SKnKSapQAjMNzaqMsrSEspOt9NMyc3LBhFVyYnHJIlbBoPyk1KISBZf88rzUSgWvIr0drIwAiCXEPEMAAAA";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(WITH_BS_BAND), NO_BS_BAND),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS_BAND} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS_BAND} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS_BAND), NO_BS_BAND),
+            (sanitize(WITH_BS_MOVIE), NO_BS_MOVIE),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS_MOVIE} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS_MOVIE} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS_MOVIE), NO_BS_MOVIE),
+            (sanitize(WITH_BS_ACTOR), NO_BS_ACTOR),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS_ACTOR} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS_ACTOR} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS_ACTOR), NO_BS_ACTOR),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_instagram() {
+        const WITH_BS: &str =
+        "https://www.instagram.com/p/DA3VayjOVSM/?utm_source=ig_web_button_share_sheet&igsh=ZDNlZDc0MzIxNw==";
+        const NO_BS: &str = "https://www.instagram.com/p/DA3VayjOVSM/";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_x_formerly_known_as_twitter() {
+        const WITH_BS: &str =
+            "https://x.com/kirawontmiss/status/1843681066282017177?s=46&t=dmXz8VbTtezubBw4-OTfRw";
+        const NO_BS: &str = "https://x.com/kirawontmiss/status/1843681066282017177";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_ebay() {
+        const WITH_BS: &str =
+            "https://www.ebay.com/itm/365052429033?mkcid=16&mkevt=1&mkrid=711-127632-2357-0&ssspo=EdIxCRi_Twa&sssrc=2047675&ssuid=14aqx1bysd2&widget_ver=artemis&media=COPY";
+        const NO_BS: &str = "https://www.ebay.com/itm/365052429033";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_walmart() {
+        const WITH_BS: &str =
+            "https://www.walmart.com/ip/Halloween-Costumes-Woman-Plus-Size-Women-Deluxe-Sexy-Women-s-Clothin-Horrific-Fancy-Dress-Costume-Distinctive-Scary-Fun-World-Fashion/8773851353?classType=VARIANT&athbdg=L1600&selectedSellerId=101685259&from=%2Fsearch&sid=21aed447-b41b-42b1-a1cd-add60fd1c945";
+        const NO_BS: &str = "https://www.walmart.com/ip/Halloween-Costumes-Woman-Plus-Size-Women-Deluxe-Sexy-Women-s-Clothin-Horrific-Fancy-Dress-Costume-Distinctive-Scary-Fun-World-Fashion/8773851353?classType=VARIANT&athbdg=L1600&selectedSellerId=101685259";
+
+        let cases = [
+            (sanitize(WITH_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {WITH_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+            (sanitize(NO_BS), NO_BS),
+            (
+                sanitize(&format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}")),
+                &format!("{LOREM_IPSUM} {NO_BS} {LOREM_IPSUM}"),
+            ),
+        ];
+
+        for (test, expected) in cases {
+            assert_eq!(test, expected);
+        }
+    }
+
+    #[test]
+    fn test_prose_boundaries() {
+        const TRACKED: &str = "https://example.com/page?id=7&utm_source=newsletter";
+        const CLEAN: &str = "https://example.com/page?id=7";
+
+        // `{}` is where the link goes
+        for template in [
+            "{}",
+            "Check this out: {}",
+            "Check this out: {}.",
+            "Have you seen {}?",
+            "Look at {}!",
+            "Wow, {}!!!",
+            "See {}, it's great",
+            "First {}; then the rest",
+            "Link: {}:",
+            "Hmm... {}...",
+            "{}?!",
+            "({})",
+            "({}).",
+            "(see {})",
+            "(see {}),",
+            "(see {}, it's great)",
+            "[{}]",
+            "[see {}].",
+            "{{{}}}",
+            "\"{}\"",
+            "\"{}\".",
+            "She said \"look at {}\", then left",
+            "'{}'",
+            "'{}',",
+            "It's at '{}'.",
+            "<{}>",
+            "<{}>.",
+            "`{}`",
+            "**{}**",
+            "*{}*.",
+            "_{}_",
+            "~{}~",
+            "{}\n",
+            "\t{}\t",
+            "line one\n{}\nline three",
+            "{} {}",
+            "{}, {}, and {}.",
+            "{}\n{}",
+            "1. {}\n2. {}",
+            "- {}\n- {}",
+            "> {}",
+            "href={}",
+            "url=\"{}\"",
+            "<a href=\"{}\">{}</a>",
+            "{\"url\": \"{}\"}",
+            "[\"{}\", \"{}\"]",
+            "({}) and ({})",
+            "Links ({}, {})",
+        ] {
+            // Can't use format! since the templates aren't literals
+            let case = template.replace("{}", TRACKED);
+            let expected = template.replace("{}", CLEAN);
+
+            assert_eq!(sanitize(&case), expected, "{template}");
+        }
+
+        for (case, expected) in [
+            (
+                "(https://en.wikipedia.org/wiki/Rust_(programming_language)?utm_source=x)",
+                "(https://en.wikipedia.org/wiki/Rust_(programming_language))",
+            ),
+            (
+                "See https://en.wikipedia.org/wiki/Rust_(programming_language)?utm_source=x.",
+                "See https://en.wikipedia.org/wiki/Rust_(programming_language).",
+            ),
+            (
+                "https://example.com/a,b/?utm_source=x, done",
+                "https://example.com/a,b/, done",
+            ),
+            (
+                "https://example.com/#!/page?, next",
+                "https://example.com/#!/page?, next",
+            ),
+            (
+                "https://example.com/it's/?utm_source=x",
+                "https://example.com/it's/",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_unicode_and_ip_hosts() {
+        for (case, expected) in [
+            (
+                "https://de.wikipedia.org/wiki/Straße?utm_source=x",
+                "https://de.wikipedia.org/wiki/Straße",
+            ),
+            (
+                "https://bücher.de/katalog?q=Straße&utm_source=x",
+                "https://bücher.de/katalog?q=Straße",
+            ),
+            (
+                "https://xn--bcher-kva.de/katalog?utm_source=x",
+                "https://xn--bcher-kva.de/katalog",
+            ),
+            ("https://例子.测试/路径?utm_source=x", "https://例子.测试/路径"),
+            (
+                "„https://bücher.de/?utm_source=x“, siehe https://de.wikipedia.org/wiki/Köln?utm_medium=x。",
+                "„https://bücher.de/“, siehe https://de.wikipedia.org/wiki/Köln。",
+            ),
+            (
+                "http://[2001:db8::1]:8080/a?utm_source=x#top",
+                "http://[2001:db8::1]:8080/a#top",
+            ),
+            ("http://192.168.0.1/?utm_source=x", "http://192.168.0.1/"),
+            ("http://[::1]", "http://[::1]"),
+            ("HTTPS://Example.COM/?utm_source=x", "HTTPS://Example.COM/"),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_nested_urls() {
+        for (case, expected) in [
+            (
+                "https://example.com/redirect?to=https%3A%2F%2Fshop.com%2Fitem%3Futm_source%3Dx%26id%3D1",
+                "https://example.com/redirect?to=https%3A%2F%2Fshop.com%2Fitem%3Fid%3D1",
+            ),
+            (
+                "https://example.com/r?u=https%253A%252F%252Fshop.com%252F%253Futm_source%253Dx&utm_medium=y",
+                "https://example.com/r?u=https%253A%252F%252Fshop.com%252F",
+            ),
+            (
+                "https://example.com/r?to=https://shop.com/?utm_source=x",
+                "https://example.com/r?to=https://shop.com/",
+            ),
+            (
+                "https://app.example.com/#/page?utm_campaign=y&tab=2",
+                "https://app.example.com/#/page?tab=2",
+            ),
+            (
+                "https://app.example.com/#/page?utm_campaign=y",
+                "https://app.example.com/#/page",
+            ),
+            (
+                "https://app.example.com/#/login?next=https%3a%2f%2fapp.example.com%2f%3futm_source%3dx",
+                "https://app.example.com/#/login?next=https%3a%2f%2fapp.example.com%2f",
+            ),
+            (
+                "https://example.com/?q=https%3A&p=100%25&r=https%3A%2F%2Fshop.com%2F",
+                "https://example.com/?q=https%3A&p=100%25&r=https%3A%2F%2Fshop.com%2F",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+
+        let (_, report) = sanitize_with_report(
+            "https://example.com/r?utm_medium=y&to=https%3A%2F%2Fshop.com%2F%3Futm_source%3Dx",
+            &SanitizationConfig::default(),
+        );
+        assert_eq!(
+            report.describe(LogRedaction::Strict),
+            "example.com (removed utm_medium); shop.com (removed utm_source)"
+        );
+    }
+
+    #[test]
+    fn test_markdown_links() {
+        for (case, expected) in [
+            (
+                "[docs](https://example.com/a?utm_source=x)",
+                "[docs](https://example.com/a)",
+            ),
+            (
+                "See [Rust](https://en.wikipedia.org/wiki/Rust_(programming_language)?utm_source=x).",
+                "See [Rust](https://en.wikipedia.org/wiki/Rust_(programming_language)).",
+            ),
+            (
+                "![logo](https://example.com/logo.png?utm_medium=x \"Logo\")",
+                "![logo](https://example.com/logo.png \"Logo\")",
+            ),
+            (
+                "[1]: https://example.com/?utm_campaign=x\n[2]: <https://example.com/b?utm_source=x> 'Two'",
+                "[1]: https://example.com/\n[2]: <https://example.com/b> 'Two'",
+            ),
+            (
+                "<https://example.com/a?id=1&utm_source=x>",
+                "<https://example.com/a?id=1>",
+            ),
+            (
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+            ),
+            (
+                "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language))",
+                "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language))",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+    }
+
+    #[test]
+    fn test_fragments() {
+        for (case, expected) in [
+            (
+                "https://example.com/page#utm_source=x&utm_medium=y",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page#section-2&utm_source=x",
+                "https://example.com/page#section-2",
+            ),
+            (
+                "https://example.com/page#cid=em-123",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page#section-2",
+                "https://example.com/page#section-2",
+            ),
+            (
+                "https://example.com/page#id=3&tab=2",
+                "https://example.com/page#id=3&tab=2",
+            ),
+            (
+                "https://example.com/page#:~:text=secret%20quote",
+                "https://example.com/page#:~:text=secret%20quote",
+            ),
+            ("https://example.com/#", "https://example.com/#"),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+
+        let config = SanitizationConfig {
+            categories: Categories {
+                context_leak: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        for (case, expected) in [
+            (
+                "https://example.com/page#:~:text=secret%20quote",
+                "https://example.com/page",
+            ),
+            (
+                "https://example.com/page#section-2:~:text=start,end&text=more",
+                "https://example.com/page#section-2",
+            ),
+            (
+                "https://app.example.com/#/page?utm_campaign=y:~:text=quote",
+                "https://app.example.com/#/page",
+            ),
+        ] {
+            assert_eq!(sanitize_with_config(case, &config), expected);
+        }
+
+        let (_, report) = sanitize_with_report(
+            "https://example.com/page#section-2:~:text=quote&utm_source=x",
+            &config,
+        );
+        assert_eq!(
+            report.describe(LogRedaction::Strict),
+            "example.com (removed :~:text)"
+        );
+    }
+
+    #[test]
+    fn test_path_rewrites() {
+        for (case, expected) in [
+            (
+                "https://shop.example/cart.jsp;jsessionid=0123ABCD?item=1",
+                "https://shop.example/cart.jsp?item=1",
+            ),
+            (
+                "https://example.com/forum/thread;PHPSESSID=abc;page=2/",
+                "https://example.com/forum/thread;page=2/",
+            ),
+            (
+                "https://www.imdb.com/title/tt0111161/ref_=nv_sr_srsg_0",
+                "https://www.imdb.com/title/tt0111161",
+            ),
+            (
+                "https://www.imdb.com/title/tt0111161/?ref_=fn_al_tt_1",
+                "https://www.imdb.com/title/tt0111161/",
+            ),
+            (
+                "https://www.aliexpress.com/_p/item/1005005869481836.html",
+                "https://www.aliexpress.com/item/1005005869481836.html",
+            ),
+            (
+                "https://www.amazon.com/ref=nav_logo",
+                "https://www.amazon.com/",
+            ),
+            (
+                "https://example.com/ref=nav/posts;id=3",
+                "https://example.com/posts;id=3",
+            ),
+            (
+                "https://example.com/?ref=newsletter&page=2",
+                "https://example.com/?page=2",
+            ),
+            (
+                "https://example.com/posts/ref-counting?reference=1",
+                "https://example.com/posts/ref-counting?reference=1",
+            ),
+        ] {
+            assert_eq!(sanitize(case), expected);
+        }
+
+        let config: SanitizationConfig = toml::from_str(
+            r#"
+            [[rules]]
+            hosts = ["example.com"]
+            category = "tracking"
+            path_rewrites = [
+                { remove_segment = "src-" },
+                { replace = { pattern = '^/p/(\d+)-[^/]*', replacement = "/p/$1" } },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let (sanitized, report) = sanitize_with_report(
+            "https://example.com/p/42-some-title/src-newsletter",
+            &config,
+        );
+        assert_eq!(sanitized, "https://example.com/p/42");
+        assert_eq!(
+            report.describe(LogRedaction::Strict),
+            "example.com (removed path segment)"
+        );
+
+        // Rules with an invalid pattern are skipped rather than rejecting the config
+        let config = toml::from_str::<SanitizationConfig>(
+            r#"rules = [
+                { category = "tracking", path_rewrites = [{ replace = { pattern = "(", replacement = "" } }] },
+                { category = "tracking", remove_params = ["src"] },
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].remove_params, ["src"]);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        // The example from the README
+        const WITH_BS: &str = "https://www.amazon.com/Inflatable-Costume-Halloween-Spacesuit-Astronaut/dp/B09BJS9BBJ?crid=11KHII13JFHSH&dib=eyJ2IjoiMSJ9.nqrP3iAjR9VsVRLmEpoJxBYxL2H53Zh_vi7fD3WLEc6FHQzxqQmlxkz-wrjBRDODNMVohaEB4LRuIWfih8XwD3xp7c-BS_ee-3hftiEMQprYJqYXYRkymkKjje28V8EDwjnQMNmDHIN3tFJUy8udIy9gWy5khyOXi_uMKVoBjqj4V5cnXAMwny9OTVr5BK2_msWm915igcvUfms6fgoQIMaIfRZlFsAX_ATpjjLUeNGLnEktxuXqogPryHsIo5o_jsbIKSyF38lJ1iDiSB13XvvAusjmkXbC0EDNT7m9n7k.WfS1pc4D_HZbaEYBi9kLOLyhRDWMoInlulOcI-kxUMc&dib_tag=se&keywords=among%2Bus&qid=1730694134&sprefix=among%2Bu%2Caps%2C118&sr=8-40&th=1";
+
+        let config = SanitizationConfig {
+            canonicalize: true,
+            ..Default::default()
+        };
+
+        for (case, expected) in [
+            (WITH_BS, "https://www.amazon.com/dp/B09BJS9BBJ"),
+            (
+                "https://www.amazon.com/gp/product/B09BJS9BBJ/ref=ppx_yo_dt_b_asin_title_o00?ie=UTF8&psc=1",
+                "https://www.amazon.com/dp/B09BJS9BBJ?ie=UTF8",
+            ),
+            (
+                "https://amazon.com/dp/B09BJS9BBJ#customerReviews",
+                "https://www.amazon.com/dp/B09BJS9BBJ#customerReviews",
+            ),
+            (
+                "https://m.youtube.com/watch?feature=shared&v=dQw4w9WgXcQ&si=abc&t=42",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+            ),
+            (
+                "https://youtu.be/dQw4w9WgXcQ?si=abc&t=47",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=47",
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1&index=2&pp=ygUIcmlja3JvbGw%3D",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1&index=2",
+            ),
+            (
+                "https://www.ebay.com/itm/Vintage-Camera-Lens/256123456789?hash=item3ba1&_trksid=p2047675",
+                "https://www.ebay.com/itm/256123456789",
+            ),
+            (
+                "https://www.aliexpress.com/_p/item/1005005869481836.html?spm=a2g0o.home&gps-id=pcJustForYou",
+                "https://www.aliexpress.com/item/1005005869481836.html",
+            ),
+            (
+                "https://www.amazon.com/dp/B09BJS9BBJ",
+                "https://www.amazon.com/dp/B09BJS9BBJ",
+            ),
+            (
+                "https://www.amazon.com/s?k=among+us&crid=3LTHOHS5L9240",
+                "https://www.amazon.com/s?k=among+us",
+            ),
+        ] {
+            assert_eq!(sanitize_with_config(case, &config), expected);
+        }
+
+        let (_, report) = sanitize_with_report(
+            "https://www.amazon.com/Costume/dp/B09BJS9BBJ?crid=2Z3A2U3LQAC8Z",
+            &config,
+        );
+        assert_eq!(
+            report.describe(LogRedaction::Strict),
+            "www.amazon.com (canonicalized, removed crid)"
+        );
+
+        // Host overrides still apply to canonical URLs
+        let config = SanitizationConfig {
+            canonicalize: true,
+            host_overrides: vec![HostOverride {
+                hosts: vec!["www.amazon.com".into()],
+                keep: vec!["th".into(), "psc".into()],
+                allow_only: None,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sanitize_with_config(WITH_BS, &config),
+            "https://www.amazon.com/dp/B09BJS9BBJ?th=1"
+        );
+
+        // Canonicalization is opt-in
+        assert_eq!(
+            sanitize(WITH_BS),
+            "https://www.amazon.com/Inflatable-Costume-Halloween-Spacesuit-Astronaut/dp/B09BJS9BBJ"
+        );
+    }
+
+    #[test]
+    fn test_disabled_categories_are_left_alone() {
+        const WITH_BS: &str =
+            "https://www.amazon.com/dp/B09BJS9BBJ?tag=someone-20&utm_source=newsletter&crid=2Z3A2U3LQAC8Z";
+
+        let config = SanitizationConfig {
+            categories: Categories {
+                campaign: false,
+                affiliate: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sanitize_with_config(WITH_BS, &config),
+            "https://www.amazon.com/dp/B09BJS9BBJ?tag=someone-20&utm_source=newsletter"
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let config = SanitizationConfig {
+            rules: vec![Rule {
+                hosts: vec!["example.com".into()],
+                remove_params: vec!["ref_src".into()],
+                remove_fragment_params: Vec::new(),
+                path_rewrites: Vec::new(),
+                canonical: None,
+                category: RuleCategory::Tracking,
+            }],
+            ..Default::default()
+        };
+
+        for (case, expected) in [
+            (
+                "https://example.com/page?ref_src=feed&id=1",
+                "https://example.com/page?id=1",
+            ),
+            (
+                "https://other.example.com/page?ref_src=feed&id=1",
+                "https://other.example.com/page?ref_src=feed&id=1",
+            ),
+        ] {
+            assert_eq!(sanitize_with_config(case, &config), expected);
+        }
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let mut config = SanitizationConfig {
+            allowlist: vec![
+                "*.corp.example.com".into(),
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ".into(),
+                "https://Intranet.example".into(),
+                "https://example.com/campaigns".into(),
+                "*.BÜCHER.de".into(),
+                "not a url://".into(),
+            ],
+            ..Default::default()
+        };
+        config.normalize();
+
+        for (case, expected) in [
+            (
+                "https://wiki.corp.example.com/page?utm_source=chat&ref=home",
+                "https://wiki.corp.example.com/page?utm_source=chat&ref=home",
+            ),
+            (
+                "https://corp.example.com/page?utm_source=chat",
+                "https://corp.example.com/page",
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=abc",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&si=abc",
+            ),
+            (
+                "https://www.youtube.com/watch?v=9bZkp7q19f0&si=abc",
+                "https://www.youtube.com/watch?v=9bZkp7q19f0",
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQxyz&si=abc",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQxyz",
+            ),
+            (
+                "https://intranet.example/page?utm_source=chat",
+                "https://intranet.example/page?utm_source=chat",
+            ),
+            // Lookalike hosts starting with an allowed URL
+            (
+                "https://intranet.example.evil.com/page?utm_source=chat",
+                "https://intranet.example.evil.com/page",
+            ),
+            (
+                "https://intranet.example:8443/page?utm_source=chat",
+                "https://intranet.example:8443/page",
+            ),
+            (
+                "http://intranet.example/page?utm_source=chat",
+                "http://intranet.example/page",
+            ),
+            (
+                "https://example.com/campaigns/spring?utm_source=chat",
+                "https://example.com/campaigns/spring?utm_source=chat",
+            ),
+            (
+                "https://example.com/campaigns-archive?utm_source=chat",
+                "https://example.com/campaigns-archive",
+            ),
+            (
+                "https://www.bücher.de/buch?utm_source=chat",
+                "https://www.bücher.de/buch?utm_source=chat",
+            ),
+            (
+                "https://www.buecher.de/buch?utm_source=chat",
+                "https://www.buecher.de/buch",
+            ),
+        ] {
+            assert_eq!(sanitize_with_config(case, &config), expected);
+        }
+    }
+
+    #[test]
+    fn test_host_overrides() {
+        let config = SanitizationConfig {
+            host_overrides: vec![
+                HostOverride {
+                    hosts: vec!["www.amazon.com".into()],
+                    keep: vec!["th".into(), "psc".into()],
+                    allow_only: None,
+                },
+                HostOverride {
+                    hosts: vec!["*youtube.com".into()],
+                    keep: vec![],
+                    allow_only: Some(vec!["v".into(), "t".into(), "list".into(), "index".into()]),
+                },
+            ],
+            ..Default::default()
+        };
+
+        for (case, expected) in [
+            (
+                "https://www.amazon.com/dp/B09BJS9BBJ?th=1&psc=1&crid=2Z3A2U3LQAC8Z",
+                "https://www.amazon.com/dp/B09BJS9BBJ?th=1&psc=1",
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42&feature=shared&pp=ygUIcmlja3JvbGw%3D",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+            ),
+            (
+                "https://youtube.com/watch?v=dQw4w9WgXcQ&list=PL1&index=2&si=abc",
+                "https://youtube.com/watch?v=dQw4w9WgXcQ&list=PL1&index=2",
+            ),
+        ] {
+            assert_eq!(sanitize_with_config(case, &config), expected);
+        }
+    }
+
+    #[test]
+    fn test_chunks() {
+        let text = "https://a.com/?utm_source=x https://b.com/ é\u{3000}https://c.com/";
+
+        for size in [1, 5, 20, 30, text.len(), text.len() * 2] {
+            let chunks: Vec<&str> = chunks(text, size).collect();
+
+            assert_eq!(chunks.concat(), text);
+            for chunk in &chunks[..chunks.len() - 1] {
+                assert!(chunk.len() >= size, "{chunk:?}");
+                // Every URL is shorter than the size, so they're never split
+                if size >= 30 {
+                    assert!(chunk.ends_with(char::is_whitespace), "{chunk:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunks_without_whitespace() {
+        let text = "é".repeat(CHUNK_SIZE * 2) + "/+=";
+
+        let chunks: Vec<&str> = chunks(&text, CHUNK_SIZE).collect();
+
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks.len() >= 2);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= CHUNK_SIZE * 2));
+    }
+
+    #[test]
+    fn test_deadline() {
+        let text = format!("{LOREM_IPSUM} https://youtu.be/dQw4w9WgXcQ?si=abc\n").repeat(10_000);
+        let config = SanitizationConfig::default();
+
+        assert_eq!(
+            sanitize_with_deadline(&text, &config, Some(Instant::now())),
+            Err(TimedOut)
+        );
+
+        let (sanitized, report) = sanitize_with_deadline(&text, &config, None).unwrap();
+        assert_eq!(report.changes.len(), 10_000);
+        assert_eq!(sanitized, text.replace("?si=abc", ""));
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_` to see how long large payloads take
+    #[test]
+    #[ignore]
+    fn bench_large_inputs() {
+        let config = SanitizationConfig::default();
+
+        for (name, line) in [
+            ("prose", format!("{LOREM_IPSUM}\n")),
+            (
+                "urls",
+                "https://www.amazon.com/dp/B09BJS9BBJ/ref=sr_1_40?crid=2Z3A2U3LQAC8Z&tag=someone-20\n".into(),
+            ),
+            (
+                "log",
+                "2024-10-13T15:09:26.123Z INFO GET https://example.com/api/items?page=2&utm_source=x 200\n".into(),
+            ),
+        ] {
+            for megabytes in [1, 10] {
+                let text = line.repeat(megabytes * 1024 * 1024 / line.len());
+
+                let start = Instant::now();
+                sanitize_with_report(&text, &config);
+
+                println!("{name} ({megabytes} MiB): {:?}", start.elapsed());
+            }
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        const WITH_BS: &str = "https://www.amazon.com/dp/B09BJS9BBJ/ref=sr_1_40?crid=2Z3A2U3LQAC8Z&tag=someone-20 https://iapetus11.me/";
+
+        let (_, report) = sanitize_with_report(WITH_BS, &SanitizationConfig::default());
+
+        assert_eq!(
+            report.changes,
+            vec![UrlChange {
+                host: "www.amazon.com".into(),
+                removed_params: vec![
+                    ("crid".into(), "2Z3A2U3LQAC8Z".into()),
+                    ("tag".into(), "someone-20".into()),
+                ],
+                path_changed: true,
+                canonicalized: false,
+            }]
+        );
+
+        assert_eq!(
+            report.describe(LogRedaction::Strict),
+            "www.amazon.com (removed crid, tag, path segment)"
+        );
+        assert!(!report.describe(LogRedaction::Hashed).contains("someone-20"));
+        assert!(report
+            .describe(LogRedaction::Off)
+            .contains("tag=someone-20"));
+    }
+}
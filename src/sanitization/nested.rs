@@ -0,0 +1,161 @@
+//! Cleans URLs nested in other URLs' parameter values, like the destination of a redirect link. These are usually
+//! percent-encoded (sometimes repeatedly), and are re-encoded exactly the same way once sanitized.
+
+use url::Url;
+
+use super::{sanitize_url_at_depth, scanner, SanitizationConfig, UrlChange};
+
+/// URLs nested deeper than this (or encoded more often) are left alone, as a safeguard against pathological input
+const MAX_NESTING_DEPTH: usize = 4;
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let is_escape = bytes[i] == b'%'
+            && bytes
+                .get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+
+        if is_escape {
+            decoded.push(u8::from_str_radix(&text[i + 1..i + 3], 16).unwrap());
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// How a layer of percent-encoding was written, so it can be reproduced
+struct Encoding {
+    /// Which bytes were percent-encoded
+    encoded: [bool; 256],
+    lowercase: bool,
+}
+
+impl Encoding {
+    fn of(text: &str) -> Self {
+        let mut encoding = Self {
+            encoded: [false; 256],
+            lowercase: false,
+        };
+
+        for (index, _) in text.match_indices('%') {
+            let Some(hex) = text.get(index + 1..index + 3) else {
+                continue;
+            };
+
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                encoding.encoded[byte as usize] = true;
+                encoding.lowercase |= hex.chars().any(|c| c.is_ascii_lowercase());
+            }
+        }
+
+        encoding
+    }
+
+    fn encode(&self, text: &str) -> String {
+        // Non-ASCII characters are either all encoded or all written as-is
+        let encodes_non_ascii = self.encoded[0x80..].contains(&true);
+
+        let mut encoded = String::with_capacity(text.len());
+        let mut buffer = [0; 4];
+
+        for c in text.chars() {
+            let encode = match u8::try_from(c).ok().filter(u8::is_ascii) {
+                Some(byte) => {
+                    self.encoded[byte as usize]
+                        || byte == b'%'
+                        || byte.is_ascii_whitespace()
+                        || byte.is_ascii_control()
+                }
+                None => encodes_non_ascii,
+            };
+
+            if !encode {
+                encoded.push(c);
+                continue;
+            }
+
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                match self.lowercase {
+                    true => encoded.push_str(&format!("%{byte:02x}")),
+                    false => encoded.push_str(&format!("%{byte:02X}")),
+                }
+            }
+        }
+
+        encoded
+    }
+}
+
+fn is_url(text: &str) -> bool {
+    scanner::find_urls(text)
+        .first()
+        .is_some_and(|url| url.start == 0)
+}
+
+/// Sanitizes a query parameter's (raw) value if it's a URL, returning the value to replace it with and what was
+/// changed, or `None` if it should be left as-is
+pub fn sanitize_nested_url(
+    value: &str,
+    config: &SanitizationConfig,
+    depth: usize,
+) -> Option<(String, Vec<UrlChange>)> {
+    if depth >= MAX_NESTING_DEPTH {
+        return None;
+    }
+
+    // Each layer of encoding, from the raw value to the nested URL
+    let mut layers = vec![value.to_string()];
+
+    while !is_url(layers.last().unwrap()) {
+        let layer = layers.last().unwrap();
+
+        if !layer.contains('%') || layers.len() > MAX_NESTING_DEPTH {
+            return None;
+        }
+
+        layers.push(percent_decode(layer)?);
+    }
+
+    let nested = layers.pop().unwrap();
+    let url = Url::parse(&nested).ok()?;
+
+    let (sanitized, changes) = sanitize_url_at_depth(&nested, url, config, depth + 1)?;
+
+    if sanitized == nested {
+        return None;
+    }
+
+    let encoded = layers.iter().rev().fold(sanitized, |sanitized, layer| {
+        Encoding::of(layer).encode(&sanitized)
+    });
+
+    Some((encoded, changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_round_trip() {
+        for encoded in [
+            "https%3A%2F%2Fshop.com%2F%3Fid%3D1",
+            "https%3a%2f%2fshop.com%2f%3fid%3d1",
+            "https://shop.com/?id=1",
+            "https%3A//shop.com/%3Fq%3Db%C3%BCcher%20und%2BCo",
+            "https%3A%2F%2Fb%C3%BCcher.de%2F",
+            "https%3A%2F%2Fbücher.de%2F",
+        ] {
+            let decoded = percent_decode(encoded).unwrap();
+            assert_eq!(Encoding::of(encoded).encode(&decoded), encoded);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::logger::{hash_for_log, LogRedaction};
+
+/// What was removed from a single URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlChange {
+    pub host: String,
+    /// Names & values of the removed query parameters
+    pub removed_params: Vec<(String, String)>,
+    /// Whether anything was removed from the URL's path
+    pub path_changed: bool,
+    /// Whether the URL was replaced with its canonical form
+    pub canonicalized: bool,
+}
+
+/// Describes what sanitization changed, without holding on to the text that was sanitized
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SanitizeReport {
+    pub changes: Vec<UrlChange>,
+}
+
+impl SanitizeReport {
+    /// Describes the changes for logging, parameter values are only included as-is when redaction is off
+    pub fn describe(&self, redaction: LogRedaction) -> String {
+        self.changes
+            .iter()
+            .map(|change| {
+                let mut removed: Vec<String> = change
+                    .removed_params
+                    .iter()
+                    .map(|(name, value)| match redaction {
+                        LogRedaction::Strict => name.clone(),
+                        LogRedaction::Hashed => format!("{name}=#{}", hash_for_log(value)),
+                        LogRedaction::Off => format!("{name}={value}"),
+                    })
+                    .collect();
+
+                if change.path_changed {
+                    removed.push("path segment".into());
+                }
+
+                let mut description = Vec::new();
+
+                if change.canonicalized {
+                    description.push("canonicalized".to_string());
+                }
+
+                if !removed.is_empty() {
+                    description.push(format!("removed {}", removed.join(", ")));
+                }
+
+                format!("{} ({})", change.host, description.join(", "))
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+}
@@ -0,0 +1,255 @@
+//! Sanitizes links in RTF documents like the ones word processors & mail clients put on the clipboard. Both
+//! `HYPERLINK` field instructions and visible text are plain text runs in RTF, so every text run is sanitized and
+//! only the runs which actually change are re-encoded, everything else is preserved byte-for-byte.
+
+use std::time::Instant;
+
+use super::{sanitize_with_deadline, SanitizationConfig, SanitizeReport, TimedOut};
+
+/// Escapes text for use in an RTF text run
+fn encode_text(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                encoded.push('\\');
+                encoded.push(c);
+            }
+            c if c.is_ascii() => encoded.push(c),
+            c => {
+                // \uN takes a signed 16-bit number followed by a fallback character for readers without Unicode
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    encoded.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Index after the control word starting at `start` (the backslash), including its parameter & delimiting space,
+/// along with the word and its parameter
+fn control_word_end(rtf: &str, start: usize) -> (usize, &str, Option<i64>) {
+    let bytes = rtf.as_bytes();
+
+    let mut i = start + 1;
+    while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+        i += 1;
+    }
+    let word = &rtf[start + 1..i];
+
+    let parameter_start = i;
+    if i < bytes.len() && bytes[i] == b'-' {
+        i += 1;
+    }
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    let parameter = rtf[parameter_start..i].parse().ok();
+
+    if i < bytes.len() && bytes[i] == b' ' {
+        i += 1;
+    }
+
+    (i, word, parameter)
+}
+
+/// A run of plain text, in its raw & decoded form
+struct TextRun {
+    start: usize,
+    text: String,
+}
+
+fn flush_run(
+    run: &mut Option<TextRun>,
+    rtf: &str,
+    end: usize,
+    output: &mut String,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+    report: &mut SanitizeReport,
+) -> Result<(), TimedOut> {
+    let Some(TextRun { start, text }) = run.take() else {
+        return Ok(());
+    };
+
+    let (sanitized, run_report) = sanitize_with_deadline(&text, config, deadline)?;
+
+    if sanitized == text {
+        output.push_str(&rtf[start..end]);
+    } else {
+        output.push_str(&encode_text(&sanitized));
+        report.changes.extend(run_report.changes);
+    }
+
+    Ok(())
+}
+
+/// Sanitizes the URLs in an RTF document's hyperlink fields & text, also returning a report of what was removed
+pub fn sanitize_rtf(rtf: &str, config: &SanitizationConfig) -> (String, SanitizeReport) {
+    sanitize_rtf_with_deadline(rtf, config, None)
+        .expect("Sanitizing without a deadline can't time out")
+}
+
+/// [`sanitize_rtf`], giving up once the deadline (if any) passed
+pub fn sanitize_rtf_with_deadline(
+    rtf: &str,
+    config: &SanitizationConfig,
+    deadline: Option<Instant>,
+) -> Result<(String, SanitizeReport), TimedOut> {
+    let bytes = rtf.as_bytes();
+
+    let mut output = String::with_capacity(rtf.len());
+    let mut report = SanitizeReport::default();
+    let mut run: Option<TextRun> = None;
+
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            // Escaped characters are part of the text
+            (b'\\', Some(escaped @ (b'\\' | b'{' | b'}'))) => {
+                run.get_or_insert(TextRun {
+                    start: i,
+                    text: String::new(),
+                })
+                .text
+                .push(*escaped as char);
+
+                i += 2;
+            }
+            (b'\\', Some(c)) if c.is_ascii_alphabetic() => {
+                flush_run(&mut run, rtf, i, &mut output, config, deadline, &mut report)?;
+
+                let (mut end, word, parameter) = control_word_end(rtf, i);
+
+                // Binary data follows \binN, which must not be interpreted
+                if let ("bin", Some(length)) = (word, parameter) {
+                    end = (end + length.max(0) as usize).min(rtf.len());
+                    while !rtf.is_char_boundary(end) {
+                        end += 1;
+                    }
+                }
+
+                output.push_str(&rtf[i..end]);
+                i = end;
+            }
+            // Control symbols, \'hh being the only one with more than one character after the backslash
+            (b'\\', next) => {
+                flush_run(&mut run, rtf, i, &mut output, config, deadline, &mut report)?;
+
+                let mut end = match next {
+                    Some(b'\'') => i + 4,
+                    Some(_) => i + 2,
+                    None => i + 1,
+                }
+                .min(rtf.len());
+                while !rtf.is_char_boundary(end) {
+                    end += 1;
+                }
+
+                output.push_str(&rtf[i..end]);
+                i = end;
+            }
+            (b'{' | b'}', _) => {
+                flush_run(&mut run, rtf, i, &mut output, config, deadline, &mut report)?;
+
+                output.push(bytes[i] as char);
+                i += 1;
+            }
+            // Line breaks are ignored by RTF readers, writers insert them anywhere (even in the middle of URLs)
+            (b'\r' | b'\n', _) => {
+                if run.is_none() {
+                    output.push(bytes[i] as char);
+                }
+
+                i += 1;
+            }
+            _ => {
+                let c = rtf[i..].chars().next().unwrap();
+
+                run.get_or_insert(TextRun {
+                    start: i,
+                    text: String::new(),
+                })
+                .text
+                .push(c);
+
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    flush_run(
+        &mut run,
+        rtf,
+        rtf.len(),
+        &mut output,
+        config,
+        deadline,
+        &mut report,
+    )?;
+
+    Ok((output, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        for (name, rtf, expected) in [
+            (
+                "hyperlink",
+                include_str!("fixtures/hyperlink.rtf"),
+                include_str!("fixtures/hyperlink.sanitized.rtf"),
+            ),
+            (
+                "wrapped",
+                include_str!("fixtures/wrapped.rtf"),
+                include_str!("fixtures/wrapped.sanitized.rtf"),
+            ),
+            (
+                "untouched",
+                include_str!("fixtures/untouched.rtf"),
+                include_str!("fixtures/untouched.rtf"),
+            ),
+        ] {
+            let (sanitized, _) = sanitize_rtf(rtf, &SanitizationConfig::default());
+            assert_eq!(sanitized, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(
+            encode_text(r"C:\a {b} Straße 😀"),
+            r"C:\\a \{b\} Stra\u223?e \u-10179?\u-8704?"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let (_, report) = sanitize_rtf(
+            include_str!("fixtures/hyperlink.rtf"),
+            &SanitizationConfig::default(),
+        );
+
+        let hosts: Vec<&str> = report.changes.iter().map(|c| c.host.as_str()).collect();
+        assert_eq!(hosts, vec!["www.youtube.com", "www.youtube.com"]);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let rtf = include_str!("fixtures/hyperlink.rtf");
+
+        assert_eq!(
+            sanitize_rtf_with_deadline(rtf, &SanitizationConfig::default(), Some(Instant::now())),
+            Err(TimedOut)
+        );
+    }
+}
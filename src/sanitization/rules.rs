@@ -0,0 +1,590 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::allowlist::{normalize_allowlist, wildcard_match};
+use crate::log;
+
+/// What kind of junk a rule removes, each category can be toggled in the config
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleCategory {
+    /// Marketing campaign parameters like `utm_source`
+    Campaign,
+    /// Share ids, search/session state & other parameters used to track users across sites
+    Tracking,
+    /// Affiliate & referral parameters which attribute purchases to someone
+    Affiliate,
+    /// Text fragments (`#:~:text=...`) which reveal what was highlighted when the link was copied
+    ContextLeak,
+}
+
+fn categories_default_enabled() -> bool {
+    true
+}
+
+/// Toggles for each [`RuleCategory`], all categories but `context_leak` are enabled by default
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Categories {
+    /// Remove marketing campaign parameters like `utm_source`
+    #[serde(default = "categories_default_enabled")]
+    pub campaign: bool,
+
+    /// Remove share ids, search/session state & other tracking parameters
+    #[serde(default = "categories_default_enabled")]
+    pub tracking: bool,
+
+    /// Remove affiliate & referral parameters
+    #[serde(default = "categories_default_enabled")]
+    pub affiliate: bool,
+
+    /// Remove text fragments, which are also used to link to a quote on purpose
+    #[serde(default)]
+    pub context_leak: bool,
+}
+
+impl Default for Categories {
+    fn default() -> Self {
+        Self {
+            campaign: true,
+            tracking: true,
+            affiliate: true,
+            context_leak: false,
+        }
+    }
+}
+
+impl Categories {
+    pub fn is_enabled(&self, category: RuleCategory) -> bool {
+        match category {
+            RuleCategory::Campaign => self.campaign,
+            RuleCategory::Tracking => self.tracking,
+            RuleCategory::Affiliate => self.affiliate,
+            RuleCategory::ContextLeak => self.context_leak,
+        }
+    }
+}
+
+/// Regular expression from the config, rules with an invalid one are skipped when loading the config
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Rewrites tracking out of a URL's path
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PathRewrite {
+    /// Removes the path segments starting with the prefix, e.g. `ref=` for `/dp/B09BJS9BBJ/ref=sr_1_1`
+    RemoveSegment(String),
+
+    /// Removes matrix parameters (`;name=value`) from path segments by case-insensitive name, e.g. `jsessionid`
+    RemoveMatrixParams(Vec<String>),
+
+    /// Replaces the matches of a regular expression in the path, the replacement can refer to capture groups (e.g.
+    /// `$1`)
+    Replace {
+        #[schemars(with = "String")]
+        pattern: Pattern,
+        replacement: String,
+    },
+}
+
+impl PathRewrite {
+    /// Rewrites a path (starting with `/` unless empty), the query & fragment aren't included
+    pub fn apply(&self, path: &str) -> String {
+        let rewritten = match self {
+            PathRewrite::RemoveSegment(prefix) => path
+                .split('/')
+                .enumerate()
+                .filter(|(i, segment)| {
+                    *i == 0 || prefix.is_empty() || !segment.starts_with(prefix.as_str())
+                })
+                .map(|(_, segment)| segment)
+                .collect::<Vec<&str>>()
+                .join("/"),
+            PathRewrite::RemoveMatrixParams(names) => path
+                .split('/')
+                .map(|segment| {
+                    let mut params = segment.split(';');
+                    let name = params.next().unwrap_or_default();
+
+                    std::iter::once(name)
+                        .chain(params.filter(|param| {
+                            let key = param.split_once('=').map_or(*param, |(key, _)| key);
+                            !names.iter().any(|name| name.eq_ignore_ascii_case(key))
+                        }))
+                        .collect::<Vec<&str>>()
+                        .join(";")
+                })
+                .collect::<Vec<String>>()
+                .join("/"),
+            PathRewrite::Replace {
+                pattern,
+                replacement,
+            } => pattern
+                .0
+                .replace_all(path, replacement.as_str())
+                .into_owned(),
+        };
+
+        // Removing every segment leaves the root
+        match rewritten.is_empty() && !path.is_empty() {
+            true => "/".to_string(),
+            false => rewritten,
+        }
+    }
+}
+
+/// Canonical form of a site's URLs, e.g. `https://www.amazon.com/dp/$id` for Amazon product links. Only the origin &
+/// path are replaced, the query & fragment are sanitized as usual.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct CanonicalUrl {
+    /// Regular expression matched against the URL's path, e.g. `/dp/(?<id>[A-Z0-9]{10})`
+    #[schemars(with = "String")]
+    pub pattern: Pattern,
+
+    /// The canonical URL, which can refer to the pattern's capture groups (e.g. `$id` or `${1}`). Query parameters
+    /// in the template come before the URL's own.
+    pub template: String,
+
+    /// Names of query parameters which aren't part of the canonical form, in addition to the ones the rules remove
+    #[serde(default)]
+    pub remove_params: Vec<String>,
+}
+
+impl CanonicalUrl {
+    /// The canonical form of a URL with the path, if the pattern matches it
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let captures = self.pattern.0.captures(path)?;
+
+        let mut canonical = String::new();
+        captures.expand(&self.template, &mut canonical);
+
+        Some(canonical)
+    }
+}
+
+/// Removes query parameters (and parameters written in the fragment) from URLs of certain hosts
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Rule {
+    /// Hosts the rule applies to (e.g. `www.example.com`), the rule applies to every host if empty
+    #[serde(default)]
+    pub hosts: Vec<String>,
+
+    /// Names of the query parameters to remove
+    #[serde(default)]
+    pub remove_params: Vec<String>,
+
+    /// Names of the parameters to remove from the fragment (e.g. `#utm_source=...&utm_medium=...`), names of
+    /// fragment directives' parameters start with `:~:` (e.g. `:~:text`)
+    #[serde(default)]
+    pub remove_fragment_params: Vec<String>,
+
+    /// Rewrites of the path, applied in order
+    #[serde(default)]
+    pub path_rewrites: Vec<PathRewrite>,
+
+    /// Canonical form of the URLs, used when `canonicalize` is enabled
+    #[serde(default)]
+    pub canonical: Option<CanonicalUrl>,
+
+    pub category: RuleCategory,
+}
+
+impl Rule {
+    fn new(hosts: &[&str], category: RuleCategory, remove_params: &[&str]) -> Self {
+        Self {
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            remove_params: remove_params.iter().map(|p| p.to_string()).collect(),
+            remove_fragment_params: Vec::new(),
+            path_rewrites: Vec::new(),
+            canonical: None,
+            category,
+        }
+    }
+
+    fn fragment(hosts: &[&str], category: RuleCategory, remove_fragment_params: &[&str]) -> Self {
+        Self {
+            remove_fragment_params: remove_fragment_params
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            ..Self::new(hosts, category, &[])
+        }
+    }
+
+    fn path(hosts: &[&str], category: RuleCategory, path_rewrites: Vec<PathRewrite>) -> Self {
+        Self {
+            path_rewrites,
+            ..Self::new(hosts, category, &[])
+        }
+    }
+
+    fn canonical(hosts: &[&str], category: RuleCategory, pattern: &str, template: &str) -> Self {
+        Self {
+            canonical: Some(CanonicalUrl {
+                pattern: Pattern(Regex::new(pattern).unwrap()),
+                template: template.to_string(),
+                remove_params: Vec::new(),
+            }),
+            ..Self::new(hosts, category, &[])
+        }
+    }
+
+    fn with_canonical_remove_params(mut self, remove_params: &[&str]) -> Self {
+        if let Some(canonical) = &mut self.canonical {
+            canonical.remove_params = remove_params.iter().map(|p| p.to_string()).collect();
+        }
+
+        self
+    }
+
+    pub fn applies_to(&self, host: &str) -> bool {
+        self.hosts.is_empty() || self.hosts.iter().any(|h| h == host)
+    }
+}
+
+/// Deserializes rules while logging & skipping invalid ones (e.g. with a pattern which isn't a valid regular
+/// expression), like invalid exclusion patterns are, rather than rejecting the whole config
+fn deserialize_rules<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Rule>, D::Error> {
+    let rules = Vec::<toml::Value>::deserialize(deserializer)?;
+
+    Ok(rules
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, rule)| match rule.try_into::<Rule>() {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                log!(Error, "Ignoring invalid rule #{}: {err}", index + 1);
+                None
+            }
+        })
+        .collect())
+}
+
+fn deserialize_optional_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Rule>>, D::Error> {
+    deserialize_rules(deserializer).map(Some)
+}
+
+/// Overrides which query parameters are removed from URLs of certain hosts, taking priority over the rules
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct HostOverride {
+    /// Hosts the override applies to, `*` matches anything (e.g. `*.youtube.com`)
+    pub hosts: Vec<String>,
+
+    /// Names of query parameters which are never removed, even if a rule would remove them
+    #[serde(default)]
+    pub keep: Vec<String>,
+
+    /// When set, every query parameter not listed here (or in `keep`) is removed
+    #[serde(default)]
+    pub allow_only: Option<Vec<String>>,
+}
+
+impl HostOverride {
+    pub fn applies_to(&self, host: &str) -> bool {
+        self.hosts
+            .iter()
+            .any(|h| wildcard_match(&h.to_lowercase(), host))
+    }
+}
+
+/// Sanitization settings which can be bundled & switched between using profiles
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq)]
+pub struct SanitizationConfig {
+    /// Which categories of rules are applied
+    #[serde(default)]
+    pub categories: Categories,
+
+    /// Additional rules applied after the built-in ones, invalid rules are ignored
+    #[serde(default, deserialize_with = "deserialize_rules")]
+    pub rules: Vec<Rule>,
+
+    /// URLs which are never touched, either by host (e.g. `*.corp.example`) or by URL prefix (e.g.
+    /// `https://example.com/campaigns/`), `*` matches anything
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+
+    /// Per-host overrides of which query parameters are kept or removed
+    #[serde(default)]
+    pub host_overrides: Vec<HostOverride>,
+
+    /// Replace the URLs of sites with known canonical forms (e.g. Amazon products) with them
+    #[serde(default)]
+    pub canonicalize: bool,
+}
+
+/// Named bundle of sanitization settings, any setting specified replaces the top-level one while the profile is
+/// active
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq)]
+pub struct SanitizationProfile {
+    /// Which categories of rules are applied
+    #[serde(default)]
+    pub categories: Option<Categories>,
+
+    /// Additional rules applied after the built-in ones, invalid rules are ignored
+    #[serde(default, deserialize_with = "deserialize_optional_rules")]
+    pub rules: Option<Vec<Rule>>,
+
+    /// URLs which are never touched, either by host or by URL prefix
+    #[serde(default)]
+    pub allowlist: Option<Vec<String>>,
+
+    /// Per-host overrides of which query parameters are kept or removed
+    #[serde(default)]
+    pub host_overrides: Option<Vec<HostOverride>>,
+
+    /// Replace the URLs of sites with known canonical forms with them
+    #[serde(default)]
+    pub canonicalize: Option<bool>,
+}
+
+impl SanitizationConfig {
+    /// Brings settings into the form they are matched in, done once when the config is loaded
+    pub fn normalize(&mut self) {
+        self.allowlist = normalize_allowlist(&self.allowlist);
+    }
+}
+
+impl SanitizationProfile {
+    /// See [`SanitizationConfig::normalize`]
+    pub fn normalize(&mut self) {
+        if let Some(allowlist) = &mut self.allowlist {
+            *allowlist = normalize_allowlist(allowlist);
+        }
+    }
+
+    pub fn apply_to(&self, base: &SanitizationConfig) -> SanitizationConfig {
+        SanitizationConfig {
+            categories: self
+                .categories
+                .clone()
+                .unwrap_or_else(|| base.categories.clone()),
+            rules: self.rules.clone().unwrap_or_else(|| base.rules.clone()),
+            allowlist: self
+                .allowlist
+                .clone()
+                .unwrap_or_else(|| base.allowlist.clone()),
+            host_overrides: self
+                .host_overrides
+                .clone()
+                .unwrap_or_else(|| base.host_overrides.clone()),
+            canonicalize: self.canonicalize.unwrap_or(base.canonicalize),
+        }
+    }
+}
+
+pub static BUILTIN_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    use RuleCategory::*;
+
+    const AMAZON: &[&str] = &["www.amazon.com", "amazon.com"];
+    const EBAY: &[&str] = &["www.ebay.com", "ebay.com"];
+    const IMDB: &[&str] = &["www.imdb.com", "imdb.com", "m.imdb.com"];
+    const YOUTUBE: &[&str] = &["www.youtube.com", "youtube.com", "m.youtube.com"];
+    const ALIEXPRESS: &[&str] = &["www.aliexpress.com", "aliexpress.com", "www.aliexpress.us"];
+    const UTM_PARAMS: &[&str] = &[
+        "utm_source",
+        "utm_medium",
+        "utm_name",
+        "utm_term",
+        "utm_content",
+        "utm_campaign",
+    ];
+
+    vec![
+        Rule::new(&[], Campaign, UTM_PARAMS),
+        Rule::fragment(&[], Campaign, UTM_PARAMS),
+        // Adobe Analytics campaign ids
+        Rule::fragment(&[], Tracking, &["cid"]),
+        Rule::fragment(&[], ContextLeak, &[":~:text"]),
+        // Referrals like `?ref=newsletter`, and path segments like Amazon's `/ref=sr_1_1` which track how a page was
+        // navigated to
+        Rule::new(&[], Affiliate, &["ref"]),
+        Rule::path(
+            &[],
+            Tracking,
+            vec![PathRewrite::RemoveSegment("ref=".into())],
+        ),
+        // Session ids of Java & PHP servers
+        Rule::path(
+            &[],
+            Tracking,
+            vec![PathRewrite::RemoveMatrixParams(vec![
+                "jsessionid".into(),
+                "phpsessid".into(),
+            ])],
+        ),
+        Rule::new(
+            &[
+                "youtu.be",
+                "www.youtube.com",
+                "youtube.com",
+                "m.youtube.com",
+            ],
+            Tracking,
+            &["si"],
+        ),
+        Rule::canonical(
+            YOUTUBE,
+            Tracking,
+            r"^/watch$",
+            "https://www.youtube.com/watch",
+        )
+        .with_canonical_remove_params(&["feature", "pp"]),
+        Rule::canonical(
+            &["youtu.be"],
+            Tracking,
+            r"^/(?<id>[\w-]{11})$",
+            "https://www.youtube.com/watch?v=$id",
+        ),
+        Rule::new(&["open.spotify.com"], Tracking, &["si"]),
+        Rule::new(
+            AMAZON,
+            Tracking,
+            &[
+                "crid",
+                "dib",
+                "dib_tag",
+                "keywords",
+                "qid",
+                "sprefix",
+                "sr",
+                "pd_rd_w",
+                "pf_rd_s",
+                "pf_rd_p",
+                "pf_rd_t",
+                "pf_rd_i",
+                "pf_rd_m",
+                "pf_rd_r",
+                "pd_rd_wg",
+                "pd_rd_r",
+                "ref",
+                "ref_",
+                "content-id",
+                "psc",
+                "th",
+            ],
+        ),
+        Rule::canonical(
+            AMAZON,
+            Tracking,
+            r"^(?:/[^/]+)?/(?:dp|gp/product)/(?<id>[A-Z0-9]{10})(?:/|$)",
+            "https://www.amazon.com/dp/$id",
+        ),
+        Rule::new(
+            AMAZON,
+            Affiliate,
+            &["linkCode", "tag", "linkId", "geniuslink"],
+        ),
+        Rule::new(
+            &["www.google.com", "google.com"],
+            Tracking,
+            &[
+                "gs_lcrp", "gs_lp", "sca_esv", "ei", "iflsig", "sclient", "rlz", "bih", "biw",
+                "dpr", "ved", "sa", "fbs", "source", "sourceid",
+            ],
+        ),
+        Rule::new(&["www.instagram.com", "instagram.com"], Tracking, &["igsh"]),
+        Rule::new(
+            &["www.x.com", "x.com", "www.twitter.com", "twitter.com"],
+            Tracking,
+            &["t", "s"],
+        ),
+        Rule::new(
+            EBAY,
+            Tracking,
+            &[
+                "_trksid",
+                "ssspo",
+                "sssrc",
+                "ssuid",
+                "widget_ver",
+                "media",
+                "hash",
+            ],
+        ),
+        Rule::new(EBAY, Affiliate, &["mkcid", "mkevt", "mkrid"]),
+        Rule::canonical(
+            EBAY,
+            Tracking,
+            r"^/itm/(?:[^/]+/)?(?<id>\d+)(?:/|$)",
+            "https://www.ebay.com/itm/$id",
+        ),
+        Rule::new(
+            &["www.walmart.com", "walmart.com"],
+            Tracking,
+            &["sid", "from"],
+        ),
+        Rule::new(IMDB, Tracking, &["ref_"]),
+        Rule::path(
+            IMDB,
+            Tracking,
+            vec![PathRewrite::RemoveSegment("ref_=".into())],
+        ),
+        Rule::new(
+            ALIEXPRESS,
+            Tracking,
+            &[
+                "spm",
+                "scm",
+                "scm-url",
+                "pvid",
+                "algo_pvid",
+                "algo_exp_id",
+                "gps-id",
+                "btsid",
+                "ws_ab_test",
+            ],
+        ),
+        Rule::new(
+            ALIEXPRESS,
+            Affiliate,
+            &[
+                "aff_fcid",
+                "aff_fsk",
+                "aff_platform",
+                "aff_trace_key",
+                "sk",
+                "terminal_id",
+            ],
+        ),
+        Rule::path(
+            ALIEXPRESS,
+            Tracking,
+            vec![PathRewrite::Replace {
+                pattern: Pattern(Regex::new("^/_p/").unwrap()),
+                replacement: "/".into(),
+            }],
+        ),
+        Rule::canonical(
+            ALIEXPRESS,
+            Tracking,
+            r"^/item/(?<id>\d+)\.html$",
+            "https://www.aliexpress.com/item/${id}.html",
+        ),
+    ]
+});
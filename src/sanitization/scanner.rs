@@ -0,0 +1,135 @@
+//! Finds URLs in prose the way linkifiers do: a URL starts at a scheme and runs up to whitespace or a character which
+//! can't be part of it, after which trailing punctuation & unbalanced closing brackets are given back to the prose.
+
+use std::ops::Range;
+
+/// Characters a URL may end with but which usually belong to the surrounding prose (or Markdown emphasis)
+const TRAILING_PUNCTUATION: [char; 19] = [
+    '.', ',', ':', ';', '!', '?', '\'', '*', '_', '~', '’', '…', '。', '，', '、', '！', '？',
+    '：', '；',
+];
+
+/// Quotes & dashes which can't be part of a URL in prose, in addition to ASCII ones
+const UNICODE_DELIMITERS: [char; 13] = [
+    '“', '”', '„', '‘', '«', '»', '‹', '›', '「', '」', '『', '』', '—',
+];
+
+/// Brackets which may be part of a URL as long as they're balanced
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+const SCHEME_SEPARATOR: &str = "://";
+
+fn is_scheme_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'.' | b'-')
+}
+
+/// Characters which end a URL, including enclosing quotes & angle brackets
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_control()
+        || matches!(c, '<' | '>' | '"' | '`')
+        || UNICODE_DELIMITERS.contains(&c)
+}
+
+/// Gives trailing punctuation & unbalanced closing brackets back to the prose
+fn trim_end(url: &str) -> &str {
+    let mut url = url;
+
+    // How many more closing than opening brackets there are, counted once as URLs can be long
+    let mut unbalanced = BRACKETS.map(|(open, close)| {
+        url.matches(close).count() as isize - url.matches(open).count() as isize
+    });
+
+    while let Some(last) = url.chars().last() {
+        let bracket = BRACKETS.iter().position(|(_, close)| last == *close);
+
+        match bracket {
+            Some(index) if unbalanced[index] > 0 => unbalanced[index] -= 1,
+            None if TRAILING_PUNCTUATION.contains(&last) => {}
+            _ => break,
+        }
+
+        url = &url[..url.len() - last.len_utf8()];
+    }
+
+    url
+}
+
+/// Ranges of the URLs in the text, which are only recognized with a scheme (`https://...`) since they couldn't be
+/// parsed without one anyway
+pub fn find_urls(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut searched_up_to = 0;
+
+    while let Some(offset) = text[searched_up_to..].find(SCHEME_SEPARATOR) {
+        let separator = searched_up_to + offset;
+        let after_separator = separator + SCHEME_SEPARATOR.len();
+
+        // Schemes start with a letter, anything before that belongs to the prose
+        let scheme_start = text.as_bytes()[searched_up_to..separator]
+            .iter()
+            .rposition(|byte| !is_scheme_char(*byte))
+            .map_or(searched_up_to, |index| searched_up_to + index + 1);
+        let start = text.as_bytes()[scheme_start..separator]
+            .iter()
+            .position(u8::is_ascii_alphabetic)
+            .map(|index| scheme_start + index);
+
+        let end = text[after_separator..]
+            .find(is_delimiter)
+            .map_or(text.len(), |index| after_separator + index);
+
+        let Some(start) = start else {
+            searched_up_to = after_separator;
+            continue;
+        };
+
+        let url = trim_end(&text[start..end]);
+
+        if start + url.len() > after_separator {
+            urls.push(start..start + url.len());
+            searched_up_to = start + url.len();
+        } else {
+            searched_up_to = after_separator;
+        }
+    }
+
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_urls() {
+        for (text, expected) in [
+            ("https://a.com", vec!["https://a.com"]),
+            ("(https://a.com/b_(c)).", vec!["https://a.com/b_(c)"]),
+            ("[https://a.com/?b=[c]]", vec!["https://a.com/?b=[c]"]),
+            (
+                "'https://a.com/b', \"https://b.com\"",
+                vec!["https://a.com/b", "https://b.com"],
+            ),
+            ("1https://a.com and ://b.com", vec!["https://a.com"]),
+            (
+                "https://a.com/?u=https://b.com",
+                vec!["https://a.com/?u=https://b.com"],
+            ),
+            ("https://...", vec![]),
+            ("git+ssh://a.com/b.git;", vec!["git+ssh://a.com/b.git"]),
+            (
+                "„https://bücher.de/Straße“—",
+                vec!["https://bücher.de/Straße"],
+            ),
+            ("(http://[::1]:80/)。", vec!["http://[::1]:80/"]),
+            (
+                "https://a.com/ x\u{a0}https://b.com",
+                vec!["https://a.com/", "https://b.com"],
+            ),
+        ] {
+            let urls: Vec<&str> = find_urls(text).into_iter().map(|url| &text[url]).collect();
+            assert_eq!(urls, expected, "{text}");
+        }
+    }
+}
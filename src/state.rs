@@ -0,0 +1,122 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::get_config_directory, log};
+
+/// App state which is persisted across restarts, kept separate from the user's config
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct State {
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    #[serde(default)]
+    pub pause: Option<Pause>,
+}
+
+/// Automatic sanitizing is paused until resumed, or until the given time
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct Pause {
+    pub until_unix_secs: Option<u64>,
+}
+
+impl Pause {
+    pub fn until_resumed() -> Self {
+        Self::default()
+    }
+
+    /// A pause for the given duration, or `None` if it would end too far in the future to be represented
+    pub fn for_duration(now: SystemTime, duration: Duration) -> Option<Self> {
+        let until = now.checked_add(duration)?;
+
+        Some(Self {
+            until_unix_secs: Some(
+                until
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            ),
+        })
+    }
+
+    pub fn until(&self) -> Option<SystemTime> {
+        self.until_unix_secs
+            .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.until().is_some_and(|until| now >= until)
+    }
+}
+
+fn get_state_file_path() -> PathBuf {
+    get_config_directory().join("state.toml")
+}
+
+pub fn load_state() -> State {
+    let state_path = get_state_file_path();
+
+    let contents = match fs::read_to_string(&state_path) {
+        Ok(contents) => contents,
+        Err(_) => return State::default(),
+    };
+
+    toml::from_str(&contents).unwrap_or_else(|err| {
+        log!(
+            Warning,
+            "Ignoring invalid state file at {state_path:#?}: {err}"
+        );
+        State::default()
+    })
+}
+
+pub fn save_state(state: &State) {
+    let state_path = get_state_file_path();
+
+    let result = toml::to_string_pretty(state)
+        .map_err(|err| err.to_string())
+        .and_then(|serialized| fs::write(&state_path, serialized).map_err(|err| err.to_string()));
+
+    if let Err(err) = result {
+        log!(Error, "Failed to save state to {state_path:#?}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_expiry() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_728_832_166);
+
+        assert!(!Pause::until_resumed().is_expired(now + Duration::from_secs(60 * 60 * 24 * 365)));
+
+        let pause = Pause::for_duration(now, Duration::from_secs(15 * 60)).unwrap();
+        assert!(!pause.is_expired(now + Duration::from_secs(14 * 60)));
+        assert!(pause.is_expired(now + Duration::from_secs(15 * 60)));
+
+        assert_eq!(Pause::for_duration(now, Duration::MAX), None);
+    }
+
+    #[test]
+    fn test_pause_round_trips_through_toml() {
+        for pause in [
+            Pause::until_resumed(),
+            Pause::for_duration(UNIX_EPOCH, Duration::from_secs(60)).unwrap(),
+        ] {
+            let state = State {
+                active_profile: None,
+                pause: Some(pause),
+            };
+
+            assert_eq!(
+                toml::from_str::<State>(&toml::to_string_pretty(&state).unwrap()).unwrap(),
+                state
+            );
+        }
+    }
+}
@@ -1,19 +1,284 @@
-use std::ffi::c_void;
-
 use windows::Win32::{
-    Foundation::{HANDLE, HGLOBAL, HWND},
+    Foundation::{GlobalFree, HANDLE, HGLOBAL, HWND},
     System::{
         DataExchange::{
-            CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
-            OpenClipboard, SetClipboardData,
+            CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, OpenClipboard,
+            RegisterClipboardFormatW, SetClipboardData,
         },
-        Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE, GMEM_ZEROINIT},
+        Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE},
     },
 };
 
-use crate::log;
+use crate::{
+    log,
+    payload::{ClipboardPayload, HTML_FORMAT, RTF_FORMAT, TEXT_FORMAT, URI_LIST_FORMAT},
+    windows::win_utils::str_as_pcwstr,
+};
 
+const CF_TEXT: u32 = 1;
+const CF_BITMAP: u32 = 2;
+const CF_METAFILEPICT: u32 = 3;
+const CF_OEMTEXT: u32 = 7;
+const CF_PALETTE: u32 = 9;
 const CF_UNICODETEXT: u32 = 13;
+const CF_ENHMETAFILE: u32 = 14;
+const CF_LOCALE: u32 = 16;
+const CF_OWNERDISPLAY: u32 = 0x0080;
+const CF_DSPBITMAP: u32 = 0x0082;
+const CF_DSPMETAFILEPICT: u32 = 0x0083;
+const CF_DSPENHMETAFILE: u32 = 0x008E;
+
+/// Formats which are handles rather than global memory (so their data can't be copied), or which Windows synthesizes
+/// from CF_UNICODETEXT and would otherwise keep the unsanitized text
+const UNCOPYABLE_FORMATS: [u32; 11] = [
+    CF_TEXT,
+    CF_BITMAP,
+    CF_METAFILEPICT,
+    CF_OEMTEXT,
+    CF_PALETTE,
+    CF_ENHMETAFILE,
+    CF_LOCALE,
+    CF_OWNERDISPLAY,
+    CF_DSPBITMAP,
+    CF_DSPMETAFILEPICT,
+    CF_DSPENHMETAFILE,
+];
+
+/// Private & GDI object formats, which are handles as well
+const HANDLE_FORMAT_RANGE: std::ops::RangeInclusive<u32> = 0x0200..=0x03FF;
+
+/// Prefix of the payload format for clipboard formats the app doesn't understand, followed by the format's id
+const WINDOWS_FORMAT_PREFIX: &str = "windows/";
+
+/// Payload format holding the header of the "HTML Format" clipboard format (e.g. its `SourceURL`), which is written
+/// back along with the HTML
+const CF_HTML_HEADER_FORMAT: &str = "windows/cf-html-header";
+
+/// Header fields of the "HTML Format" clipboard format which are byte offsets into it, in the order they're written
+const CF_HTML_OFFSET_FIELDS: [&str; 6] = [
+    "StartHTML",
+    "EndHTML",
+    "StartFragment",
+    "EndFragment",
+    "StartSelection",
+    "EndSelection",
+];
+
+fn is_copyable(format: u32) -> bool {
+    !UNCOPYABLE_FORMATS.contains(&format) && !HANDLE_FORMAT_RANGE.contains(&format)
+}
+
+fn registered_format(name: &str) -> u32 {
+    unsafe { RegisterClipboardFormatW(str_as_pcwstr(name).value) }
+}
+
+fn trim_nul(data: &[u8]) -> Vec<u8> {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    data[..end].to_vec()
+}
+
+fn utf16_to_utf8(data: &[u8]) -> Vec<u8> {
+    let utf16: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    // The clipboard data's size includes the null terminator (and possibly some padding)
+    String::from_utf16_lossy(&utf16)
+        .trim_end_matches('\0')
+        .as_bytes()
+        .to_vec()
+}
+
+fn utf8_to_utf16(data: &[u8]) -> Vec<u8> {
+    String::from_utf8_lossy(data)
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The "UniformResourceLocator" format holds the URL in the ANSI code page, non-ASCII characters are percent-encoded
+/// so it stays valid in any code page
+fn ansi_url_from_uri_list(data: &[u8]) -> Vec<u8> {
+    let mut ansi = Vec::with_capacity(data.len() + 1);
+
+    for byte in data {
+        match byte.is_ascii() {
+            true => ansi.push(*byte),
+            false => ansi.extend_from_slice(format!("%{byte:02X}").as_bytes()),
+        }
+    }
+
+    ansi.push(0);
+    ansi
+}
+
+/// Splits the "HTML Format" clipboard format into its header (of byte offsets, the source URL, ...) and the HTML
+/// document
+fn split_cf_html(data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let data = trim_nul(data);
+    let header_bytes = &data[..find(&data, b"<").unwrap_or(data.len())];
+    let header = String::from_utf8_lossy(header_bytes);
+
+    let offset = |name: &str| {
+        header.lines().find_map(|line| {
+            line.strip_prefix(name)?
+                .strip_prefix(':')?
+                .trim()
+                .parse::<usize>()
+                .ok()
+        })
+    };
+
+    let start = offset("StartHTML")
+        .or(offset("StartFragment"))
+        .filter(|start| *start <= data.len())
+        .unwrap_or(0);
+    let end = offset("EndHTML")
+        .filter(|end| (start..=data.len()).contains(end))
+        .unwrap_or(data.len());
+
+    (header_bytes.to_vec(), data[start..end].to_vec())
+}
+
+/// Wraps an HTML document into the "HTML Format" clipboard format, the fragment being marked by the
+/// `<!--StartFragment-->` & `<!--EndFragment-->` comments (or the whole document without them). The original header's
+/// fields are kept (if there is one), only the offsets are recomputed.
+fn cf_html_from_html(html: &[u8], original_header: Option<&[u8]>) -> Vec<u8> {
+    const START_MARKER: &[u8] = b"<!--StartFragment-->";
+    const END_MARKER: &[u8] = b"<!--EndFragment-->";
+
+    let original_header = original_header
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    let is_offset_field = |line: &str| {
+        line.split_once(':')
+            .is_some_and(|(name, _)| CF_HTML_OFFSET_FIELDS.contains(&name.trim()))
+    };
+
+    let version = original_header
+        .lines()
+        .find(|line| line.starts_with("Version:"))
+        .unwrap_or("Version:0.9");
+    let other_fields: Vec<&str> = original_header
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with("Version:") && !is_offset_field(line))
+        .collect();
+    let has_selection = original_header
+        .lines()
+        .any(|line| line.starts_with("StartSelection:"));
+
+    // The offsets are zero-padded so the header's length doesn't depend on them. The selection is the fragment.
+    let header = |[start_html, end_html, start_fragment, end_fragment]: [usize; 4]| {
+        let offsets = [
+            start_html,
+            end_html,
+            start_fragment,
+            end_fragment,
+            start_fragment,
+            end_fragment,
+        ];
+        let field_count = if has_selection { 6 } else { 4 };
+
+        let mut header = format!("{version}\r\n");
+        for (name, offset) in CF_HTML_OFFSET_FIELDS.iter().zip(offsets).take(field_count) {
+            header += &format!("{name}:{offset:010}\r\n");
+        }
+        for field in &other_fields {
+            header += &format!("{field}\r\n");
+        }
+
+        header
+    };
+
+    let header_len = header([0; 4]).len();
+
+    let start_fragment = find(html, START_MARKER)
+        .map(|index| index + START_MARKER.len())
+        .unwrap_or(0);
+    let end_fragment = find(html, END_MARKER)
+        .filter(|end| *end >= start_fragment)
+        .unwrap_or(html.len());
+
+    let mut data = header([
+        header_len,
+        header_len + html.len(),
+        header_len + start_fragment,
+        header_len + end_fragment,
+    ])
+    .into_bytes();
+    data.extend_from_slice(html);
+    data.push(0);
+
+    data
+}
+
+/// Copies the contents of a clipboard data handle, which must be global memory
+unsafe fn read_global(handle: HANDLE) -> Option<Vec<u8>> {
+    let global = HGLOBAL(handle.0);
+
+    let size = GlobalSize(global);
+    let locked = GlobalLock(global);
+
+    if locked.is_null() {
+        log!(Debug, "Clipboard data is null");
+        return None;
+    }
+
+    let data = std::slice::from_raw_parts(locked as *const u8, size).to_vec();
+
+    // Returns an "error" once the memory isn't locked anymore
+    let _ = GlobalUnlock(global);
+
+    Some(data)
+}
+
+/// Allocates global memory with the data, whose ownership passes to the clipboard once set
+unsafe fn alloc_global(data: &[u8]) -> windows::core::Result<HANDLE> {
+    let global = GlobalAlloc(GMEM_MOVEABLE, data.len().max(1))?;
+
+    let locked = GlobalLock(global);
+    if !locked.is_null() {
+        data.as_ptr()
+            .copy_to_nonoverlapping(locked as *mut u8, data.len());
+    }
+    let _ = GlobalUnlock(global);
+
+    Ok(HANDLE(global.0))
+}
+
+/// Frees global memory which wasn't handed over to the clipboard
+unsafe fn free_globals(handles: &[(u32, HANDLE)]) {
+    for (_, handle) in handles {
+        let _ = GlobalFree(Some(HGLOBAL(handle.0)));
+    }
+}
+
+/// Ids of the registered clipboard formats which are converted from & to payload formats
+struct RegisteredFormats {
+    html: u32,
+    rtf: u32,
+    url: u32,
+    ansi_url: u32,
+}
+
+impl RegisteredFormats {
+    fn get() -> Self {
+        Self {
+            html: registered_format("HTML Format"),
+            rtf: registered_format("Rich Text Format"),
+            url: registered_format("UniformResourceLocatorW"),
+            ansi_url: registered_format("UniformResourceLocator"),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Clipboard {
@@ -49,78 +314,145 @@ impl Clipboard {
 
     fn with_clipboard<T>(
         &mut self,
-        execute: Box<dyn Fn() -> windows::core::Result<T>>,
+        execute: impl FnOnce() -> windows::core::Result<T>,
     ) -> windows::core::Result<T> {
         self.open()?;
 
-        let result = execute()?;
+        // The clipboard has to be closed even if something failed, otherwise no other app could open it
+        let result = execute();
 
         self.close()?;
 
-        Ok(result)
+        result
     }
 
-    pub fn get_text(&mut self) -> windows::core::Result<Option<String>> {
-        let clipboard_data = self.with_clipboard(Box::new(|| {
-            if unsafe { IsClipboardFormatAvailable(CF_UNICODETEXT) }.is_err() {
-                log!(Debug, "Clipboard for CF_UNICODETEXT is not available");
-                return Ok(None);
-            }
+    /// Reads every format on the clipboard whose data can be copied
+    pub fn get_payload(&mut self) -> windows::core::Result<ClipboardPayload> {
+        let formats = RegisteredFormats::get();
+
+        self.with_clipboard(|| {
+            let mut payload = ClipboardPayload::default();
+            let mut ansi_url = None;
+            let mut format = 0;
 
-            let clipboard_data = unsafe { GetClipboardData(CF_UNICODETEXT) }?.0;
+            loop {
+                format = unsafe { EnumClipboardFormats(format) };
 
-            if clipboard_data.is_null() {
-                log!(Debug, "Clipboard data is null");
-                return Ok(None);
+                if format == 0 {
+                    break;
+                }
+
+                if !is_copyable(format) {
+                    continue;
+                }
+
+                let Ok(handle) = (unsafe { GetClipboardData(format) }) else {
+                    log!(Debug, "Clipboard data for format {format} is not available");
+                    continue;
+                };
+
+                let Some(data) = (unsafe { read_global(handle) }) else {
+                    continue;
+                };
+
+                match format {
+                    CF_UNICODETEXT => payload.push(TEXT_FORMAT, utf16_to_utf8(&data)),
+                    format if format == formats.html => {
+                        let (header, html) = split_cf_html(&data);
+                        payload.push(CF_HTML_HEADER_FORMAT, header);
+                        payload.push(HTML_FORMAT, html);
+                    }
+                    format if format == formats.rtf => payload.push(RTF_FORMAT, trim_nul(&data)),
+                    format if format == formats.url => {
+                        payload.push(URI_LIST_FORMAT, utf16_to_utf8(&data))
+                    }
+                    format if format == formats.ansi_url => {
+                        ansi_url = Some(String::from_utf8_lossy(&trim_nul(&data)).into_owned())
+                    }
+                    format => payload.push(&format!("{WINDOWS_FORMAT_PREFIX}{format}"), data),
+                }
             }
 
-            let clipboard_data = HGLOBAL(clipboard_data);
+            // The ANSI URL is written along with the wide one, so it's only read when there is no wide one
+            if let Some(ansi_url) = ansi_url.filter(|_| payload.get(URI_LIST_FORMAT).is_none()) {
+                payload.push(URI_LIST_FORMAT, ansi_url.into_bytes());
+            }
 
-            let clipboard_data = unsafe {
-                let clipboard_data_size = GlobalSize(clipboard_data);
+            Ok(payload)
+        })
+    }
 
-                let mut string_data = vec![0_u16; clipboard_data_size / size_of::<u16>()];
+    /// Replaces the clipboard's contents with the payload's formats, an empty payload clears the clipboard
+    pub fn set_payload(&mut self, payload: &ClipboardPayload) -> windows::core::Result<()> {
+        let formats = RegisteredFormats::get();
 
-                let locked_clipboard_data = GlobalLock(clipboard_data);
-                if locked_clipboard_data.is_null() {
-                    log!(Debug, "Clipboard data is null");
-                    return Ok(None);
+        let mut contents = Vec::new();
+
+        for representation in &payload.representations {
+            match representation.format.as_str() {
+                TEXT_FORMAT => contents.push((CF_UNICODETEXT, utf8_to_utf16(&representation.data))),
+                HTML_FORMAT => contents.push((
+                    formats.html,
+                    cf_html_from_html(&representation.data, payload.get(CF_HTML_HEADER_FORMAT)),
+                )),
+                CF_HTML_HEADER_FORMAT => continue,
+                RTF_FORMAT => {
+                    let mut data = representation.data.clone();
+                    data.push(0);
+                    contents.push((formats.rtf, data));
+                }
+                URI_LIST_FORMAT => {
+                    contents.push((formats.url, utf8_to_utf16(&representation.data)));
+                    contents.push((
+                        formats.ansi_url,
+                        ansi_url_from_uri_list(&representation.data),
+                    ));
                 }
+                other => {
+                    let Some(format) = other
+                        .strip_prefix(WINDOWS_FORMAT_PREFIX)
+                        .and_then(|format| format.parse().ok())
+                    else {
+                        log!(Debug, "Skipped unknown clipboard format {other}");
+                        continue;
+                    };
 
-                locked_clipboard_data.copy_to(
-                    string_data.as_mut_ptr().cast::<c_void>(),
-                    clipboard_data_size,
-                );
+                    contents.push((format, representation.data.clone()));
+                }
+            }
+        }
 
-                GlobalUnlock(clipboard_data)?;
+        // Everything is allocated before the clipboard is emptied, so running out of memory leaves it as it was
+        let mut handles = Vec::with_capacity(contents.len());
 
-                string_data
-            };
+        for (format, data) in &contents {
+            match unsafe { alloc_global(data) } {
+                Ok(handle) => handles.push((*format, handle)),
+                Err(err) => {
+                    unsafe { free_globals(&handles) };
+                    return Err(err);
+                }
+            }
+        }
 
-            Ok(Some(clipboard_data))
-        }))?;
+        // The clipboard owns the memory of every format which was set
+        let mut handed_over = 0;
 
-        Ok(clipboard_data.map(|cpd| String::from_utf16_lossy(&cpd)))
-    }
+        let result = self.with_clipboard(|| {
+            unsafe { EmptyClipboard() }?;
 
-    pub fn set_text(&mut self, text: String) -> windows::core::Result<()> {
-        self.with_clipboard(Box::new(move || {
-            unsafe {
-                let text_utf16 = text.encode_utf16().collect::<Vec<u16>>();
-                let text_size = text_utf16.len() * std::mem::size_of::<u16>();
-
-                let text_handle_mem =
-                    GlobalLock(GlobalAlloc(GMEM_MOVEABLE | GMEM_ZEROINIT, text_size + 1)?);
-                text_utf16
-                    .as_ptr()
-                    .copy_to_nonoverlapping(text_handle_mem as *mut u16, text_utf16.len());
-                GlobalUnlock(HGLOBAL(text_handle_mem))?;
-
-                EmptyClipboard()?;
-                SetClipboardData(CF_UNICODETEXT, Some(HANDLE(text_handle_mem)))?;
+            for (format, handle) in &handles {
+                unsafe { SetClipboardData(*format, Some(*handle)) }?;
+                handed_over += 1;
             }
 
             Ok(())
-        }))
+        });
+
+        if result.is_err() {
+            unsafe { free_globals(&handles[handed_over..]) };
+        }
+
+        result
     }
 }
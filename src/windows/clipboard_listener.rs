@@ -3,32 +3,35 @@ use windows::Win32::{
     System::DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener},
 };
 
-use crate::{log, sanitization::sanitize, windows::clipboard::Clipboard};
-
-pub fn handle_clipboard_changed(hwnd: HWND) -> LRESULT {
-    let mut clipboard = Clipboard::new(hwnd);
-
-    if let Ok(Some(contents)) = clipboard.get_text() {
-        let sanitized_contents = sanitize(&contents);
-
-        if contents == sanitized_contents {
-            return LRESULT(0);
-        }
+use crate::{
+    app_core::SharedAppCore, log, payload::ClipboardPayload, watcher::ClipboardBackend,
+    windows::clipboard::Clipboard,
+};
 
-        let result = clipboard.set_text(sanitized_contents);
+impl ClipboardBackend for Clipboard {
+    fn get_payload(&mut self) -> Option<ClipboardPayload> {
+        Clipboard::get_payload(self).ok()
+    }
 
-        match result {
-            Ok(_) => log!(Info, "Sanitized copied text!"),
-            Err(err) => log!(
-                Error,
-                "Failed to set clipboard with sanitized text due to: {err}"
-            ),
-        }
+    fn set_payload(&mut self, payload: &ClipboardPayload) -> Result<(), String> {
+        Clipboard::set_payload(self, payload).map_err(|err| err.to_string())
     }
+}
+
+pub fn handle_clipboard_changed(hwnd: HWND, core: &SharedAppCore) -> LRESULT {
+    core.lock()
+        .unwrap()
+        .handle_clipboard_changed(&mut Clipboard::new(hwnd));
 
     LRESULT(0)
 }
 
+/// Performs the app's delayed work like restoring text requested through IPC, which has to happen on the window's
+/// thread
+pub fn tick(hwnd: HWND, core: &SharedAppCore) {
+    core.lock().unwrap().tick(&mut Clipboard::new(hwnd));
+}
+
 pub fn setup_clipboard_listener(hwnd: HWND) -> windows::core::Result<()> {
     let result = unsafe { AddClipboardFormatListener(hwnd) };
 
@@ -1,12 +1,13 @@
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use windows::Win32::UI::Shell::{SHGetFolderPathW, CSIDL_PROFILE};
+use windows::Win32::UI::Shell::{SHGetFolderPathW, CSIDL_COMMON_APPDATA, CSIDL_PROFILE};
 
-pub fn get_home_directory() -> PathBuf {
+fn get_folder_path(csidl: u32) -> PathBuf {
     PathBuf::from(unsafe {
         let mut out = [0_u16; 260];
-        SHGetFolderPathW(None, CSIDL_PROFILE as i32, None, 0, &mut out).unwrap();
+        SHGetFolderPathW(None, csidl as i32, None, 0, &mut out).unwrap();
 
         String::from_utf16_lossy(
             &out.into_iter()
@@ -16,5 +17,14 @@ pub fn get_home_directory() -> PathBuf {
     })
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub fn get_home_directory() -> PathBuf {
+    get_folder_path(CSIDL_PROFILE)
+}
+
+/// Directory containing the machine-wide config, e.g. `C:\ProgramData\clipboard_cleanse`
+pub fn get_system_config_directory() -> PathBuf {
+    get_folder_path(CSIDL_COMMON_APPDATA).join("clipboard_cleanse")
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
 pub struct Config {}
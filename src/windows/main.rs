@@ -4,10 +4,11 @@ use std::error::Error;
 use std::time::Duration;
 use std::{fs, process, thread};
 
-use crate::log;
+use crate::app_core::{AppCore, SharedAppCore};
 use crate::windows::get_home_directory;
-use crate::windows::system_tray::destroy_system_tray_item;
+use crate::windows::system_tray::{destroy_system_tray_item, show_system_tray_balloon};
 use crate::Config;
+use crate::{crash, ipc, log};
 
 use windows::Win32::UI::Shell::NOTIFYICONDATAW;
 use windows::Win32::UI::WindowsAndMessaging::{
@@ -21,7 +22,7 @@ use windows::Win32::{
 };
 
 use super::clipboard_listener::{
-    destroy_clipboard_listener, handle_clipboard_changed, setup_clipboard_listener,
+    destroy_clipboard_listener, handle_clipboard_changed, setup_clipboard_listener, tick,
 };
 use super::ctrlc_handler::setup_ctrlc_handler;
 use super::menu::{setup_menu, show_menu_and_handle_action};
@@ -33,7 +34,7 @@ use super::wm_user::WmUser;
 
 #[derive(Clone)]
 struct App {
-    config: Config,
+    core: SharedAppCore,
     hwnd: HWND,
     nid: NOTIFYICONDATAW,
     menu: HMENU,
@@ -41,7 +42,8 @@ struct App {
 
 static mut APP: Option<App> = None;
 
-pub fn process_win32_events_forever(hwnd: HWND) {
+fn process_win32_events_forever(app: &App) {
+    let hwnd = app.hwnd;
     let sleep_duration = Duration::from_millis(200);
 
     log!(Debug, "Running event loop...");
@@ -54,6 +56,8 @@ pub fn process_win32_events_forever(hwnd: HWND) {
             }
         }
 
+        tick(hwnd, &app.core);
+
         // Don't want to consume so many cpu cycles
         thread::sleep(sleep_duration);
     }
@@ -105,11 +109,39 @@ fn process_wm_command(app: App, cmd: WmCommand) -> Result<(), Box<dyn Error>> {
             }
         }
         WmCommand::MenuOpenConfigFile => {
+            let config_path = app.core.lock().unwrap().config.config_path.clone();
+
             process::Command::new("cmd")
-                .args(["/c", "start", &app.config.config_path])
+                .args(["/c", "start", &config_path])
                 .output()
                 .unwrap();
         }
+        WmCommand::MenuSelectProfile(index) => {
+            let mut core = app.core.lock().unwrap();
+
+            let profile = core
+                .profile_menu_entries()
+                .get(index)
+                .ok_or(format!("No profile menu entry at {index}"))?
+                .profile
+                .clone();
+
+            core.set_active_profile(profile.as_deref())?;
+        }
+        WmCommand::MenuTogglePause => {
+            let mut core = app.core.lock().unwrap();
+
+            match core.is_paused() {
+                true => core.resume(),
+                false => core.pause(None)?,
+            }
+        }
+        WmCommand::MenuPauseFor(minutes) => {
+            app.core
+                .lock()
+                .unwrap()
+                .pause(Some(Duration::from_secs(minutes * 60)))?;
+        }
         WmCommand::MenuQuit => unsafe {
             PostMessageW(Some(app.hwnd), WM_QUIT, WPARAM(0), LPARAM(0)).unwrap();
         },
@@ -120,13 +152,20 @@ fn process_wm_command(app: App, cmd: WmCommand) -> Result<(), Box<dyn Error>> {
 
 extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     match msg {
-        msg if msg == WM_CLIPBOARDUPDATE => handle_clipboard_changed(hwnd),
+        msg if msg == WM_CLIPBOARDUPDATE => match unsafe { APP.clone() } {
+            Some(app) => handle_clipboard_changed(hwnd, &app.core),
+            None => LRESULT(0),
+        },
         msg if msg == WmUser::ShellIcon as u32 => {
             let lparam = lparam.0 as u32;
 
             if lparam == WM_LBUTTONDOWN || lparam == WM_RBUTTONDOWN {
                 let app = unsafe { APP.clone().unwrap() };
-                show_menu_and_handle_action(hwnd, app.menu).unwrap();
+                let (profile_entries, paused) = {
+                    let core = app.core.lock().unwrap();
+                    (core.profile_menu_entries(), core.is_paused())
+                };
+                show_menu_and_handle_action(hwnd, app.menu, &profile_entries, paused).unwrap();
             }
 
             LRESULT(0)
@@ -155,17 +194,20 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM
 }
 
 fn setup(config: Config) -> Result<App, Box<dyn error::Error>> {
+    let core = AppCore::new(config).shared();
+    ipc::start_server(core.clone());
+
     let hwnd = init_window(wnd_proc)?;
     setup_ctrlc_handler(hwnd)?;
     setup_clipboard_listener(hwnd)?;
     let nid = setup_system_tray_item(hwnd)?;
-    let menu = setup_menu()?;
+    let menu = setup_menu(&core.lock().unwrap().profile_menu_entries())?;
 
     Ok(App {
         hwnd,
         nid,
         menu,
-        config,
+        core,
     })
 }
 
@@ -186,7 +228,15 @@ pub fn main(config: Config) {
         APP = Some(app.clone());
     }
 
-    process_win32_events_forever(app.hwnd);
+    if let Some(report_path) = crash::report_previous_crash() {
+        show_system_tray_balloon(
+            &app.nid,
+            crash::CRASH_NOTIFICATION_TITLE,
+            &crash::crash_notification_text(&report_path),
+        );
+    }
+
+    process_win32_events_forever(&app);
 
     destroy(&app).unwrap();
 }
@@ -1,18 +1,23 @@
+use windows::core::PCWSTR;
 use windows::Win32::{
     Foundation::{GetLastError, HWND},
     UI::WindowsAndMessaging::{
-        CreatePopupMenu, InsertMenuW, SendMessageW, TrackPopupMenu, HMENU, MF_BYPOSITION,
-        MF_STRING, TPM_BOTTOMALIGN, TPM_HORNEGANIMATION, TPM_LEFTALIGN, TPM_RIGHTBUTTON,
-        WM_CANCELMODE,
+        CheckMenuItem, CreatePopupMenu, InsertMenuW, SendMessageW, TrackPopupMenu, HMENU,
+        MF_BYCOMMAND, MF_BYPOSITION, MF_CHECKED, MF_SEPARATOR, MF_STRING, MF_UNCHECKED,
+        TPM_BOTTOMALIGN, TPM_HORNEGANIMATION, TPM_LEFTALIGN, TPM_RIGHTBUTTON, WM_CANCELMODE,
     },
 };
 use windows_result::HRESULT;
 
-use crate::{log, windows::system_tray::get_system_tray_item_rect};
+use crate::{
+    app_core::{pause_menu_label, ProfileMenuEntry, PAUSE_MENU_MINUTES},
+    log,
+    windows::system_tray::get_system_tray_item_rect,
+};
 
 use super::{win_utils::str_as_pcwstr, wm_command::WmCommand};
 
-pub fn setup_menu() -> windows::core::Result<HMENU> {
+pub fn setup_menu(profile_entries: &[ProfileMenuEntry]) -> windows::core::Result<HMENU> {
     unsafe {
         let menu = CreatePopupMenu()?;
 
@@ -32,6 +37,61 @@ pub fn setup_menu() -> windows::core::Result<HMENU> {
             str_as_pcwstr("Open Config File").value,
         )?;
 
+        InsertMenuW(
+            menu,
+            0xFFFFFFFF,
+            MF_BYPOSITION | MF_SEPARATOR,
+            0,
+            PCWSTR::null(),
+        )?;
+
+        InsertMenuW(
+            menu,
+            0xFFFFFFFF,
+            MF_BYPOSITION | MF_STRING,
+            WmCommand::MenuTogglePause.into(),
+            str_as_pcwstr("Paused").value,
+        )?;
+
+        for minutes in PAUSE_MENU_MINUTES {
+            InsertMenuW(
+                menu,
+                0xFFFFFFFF,
+                MF_BYPOSITION | MF_STRING,
+                WmCommand::MenuPauseFor(minutes).into(),
+                str_as_pcwstr(&pause_menu_label(minutes)).value,
+            )?;
+        }
+
+        InsertMenuW(
+            menu,
+            0xFFFFFFFF,
+            MF_BYPOSITION | MF_SEPARATOR,
+            0,
+            PCWSTR::null(),
+        )?;
+
+        // Only the "No Profile" entry, so no profiles are configured
+        if profile_entries.len() > 1 {
+            for (index, entry) in profile_entries.iter().enumerate() {
+                InsertMenuW(
+                    menu,
+                    0xFFFFFFFF,
+                    MF_BYPOSITION | MF_STRING,
+                    WmCommand::MenuSelectProfile(index).into(),
+                    str_as_pcwstr(&entry.label).value,
+                )?;
+            }
+
+            InsertMenuW(
+                menu,
+                0xFFFFFFFF,
+                MF_BYPOSITION | MF_SEPARATOR,
+                0,
+                PCWSTR::null(),
+            )?;
+        }
+
         InsertMenuW(
             menu,
             0xFFFFFFFF,
@@ -44,9 +104,33 @@ pub fn setup_menu() -> windows::core::Result<HMENU> {
     }
 }
 
-pub fn show_menu_and_handle_action(hwnd: HWND, menu: HMENU) -> windows::core::Result<()> {
+fn check_menu_item(menu: HMENU, cmd: WmCommand, checked: bool) {
+    let check = match checked {
+        true => MF_CHECKED,
+        false => MF_UNCHECKED,
+    };
+
+    unsafe { CheckMenuItem(menu, usize::from(cmd) as u32, (MF_BYCOMMAND | check).0) };
+}
+
+/// Updates the checkmarks of the profile menu items to reflect the active profile
+fn update_profile_menu_items(menu: HMENU, profile_entries: &[ProfileMenuEntry]) {
+    for (index, entry) in profile_entries.iter().enumerate() {
+        check_menu_item(menu, WmCommand::MenuSelectProfile(index), entry.active);
+    }
+}
+
+pub fn show_menu_and_handle_action(
+    hwnd: HWND,
+    menu: HMENU,
+    profile_entries: &[ProfileMenuEntry],
+    paused: bool,
+) -> windows::core::Result<()> {
     log!(Debug, "Showing system tray icon menu...");
 
+    update_profile_menu_items(menu, profile_entries);
+    check_menu_item(menu, WmCommand::MenuTogglePause, paused);
+
     let rect = get_system_tray_item_rect(hwnd);
 
     if rect.is_err() {
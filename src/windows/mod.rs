@@ -12,5 +12,6 @@ mod window;
 mod wm_command;
 mod wm_user;
 
-pub use config::{get_home_directory, Config};
+pub use config::{get_home_directory, get_system_config_directory, Config};
 pub use main::main;
+pub use win_utils::attach_parent_console;
@@ -7,8 +7,9 @@ use windows::{
         System::LibraryLoader::GetModuleHandleW,
         UI::{
             Shell::{
-                Shell_NotifyIconGetRect, Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP,
-                NIM_ADD, NIM_DELETE, NOTIFYICONDATAW, NOTIFYICONIDENTIFIER,
+                Shell_NotifyIconGetRect, Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE,
+                NIF_TIP, NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
+                NOTIFYICONIDENTIFIER,
             },
             WindowsAndMessaging::LoadIconW,
         },
@@ -62,6 +63,29 @@ pub fn destroy_system_tray_item(nid: &NOTIFYICONDATAW) {
     }
 }
 
+/// Shows a warning balloon (a toast on newer Windows versions) from the system tray item, the text is cut off if it
+/// doesn't fit
+pub fn show_system_tray_balloon(nid: &NOTIFYICONDATAW, title: &str, text: &str) {
+    let mut info = [0u16; 256];
+    for (unit, text_unit) in info.iter_mut().zip(text.encode_utf16().take(255)) {
+        *unit = text_unit;
+    }
+
+    let balloon_nid = NOTIFYICONDATAW {
+        uFlags: NIF_INFO,
+        szInfo: info,
+        szInfoTitle: str_to_u16_nul_term_array::<64>(title).unwrap(),
+        dwInfoFlags: NIIF_WARNING,
+        ..*nid
+    };
+
+    let success = unsafe { Shell_NotifyIconW(NIM_MODIFY, &balloon_nid) }.as_bool();
+
+    if !success {
+        log!(Error, "Failed to show system tray balloon :/");
+    }
+}
+
 pub fn get_system_tray_item_rect(hwnd: HWND) -> windows::core::Result<RECT> {
     let identifier = NOTIFYICONIDENTIFIER {
         cbSize: size_of::<NOTIFYICONIDENTIFIER>() as u32,
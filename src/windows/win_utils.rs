@@ -1,4 +1,7 @@
-use windows::core::PCWSTR;
+use windows::{
+    core::PCWSTR,
+    Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS},
+};
 
 /// Takes a &str and returns a fixed-length null terminated array of u16s, useful for some win32 apis
 pub fn str_to_u16_nul_term_array<const N: usize>(str: &str) -> Result<[u16; N], String> {
@@ -33,3 +36,9 @@ pub fn str_as_pcwstr(str: &str) -> RetainedPCWSTR {
         _encoded: encoded,
     }
 }
+
+/// Attaches to the console of the parent process (if any) so that command output is visible, as release builds use
+/// the windows subsystem and don't get a console of their own
+pub fn attach_parent_console() {
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
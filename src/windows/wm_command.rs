@@ -1,9 +1,18 @@
+/// Menu item ids from this up to the profile base pause sanitizing for `id - base` minutes
+const MENU_PAUSE_FOR_BASE: usize = 100;
+
+/// Menu item ids at or above this select the profile at `id - base` in the tray menu's profile entries
+const MENU_SELECT_PROFILE_BASE: usize = 1000;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum WmCommand {
-    MenuToggleAutoStart = 1,
-    MenuOpenConfigFile = 2,
-    MenuQuit = 3,
+    MenuToggleAutoStart,
+    MenuOpenConfigFile,
+    MenuQuit,
+    MenuTogglePause,
+    MenuPauseFor(u64),
+    MenuSelectProfile(usize),
 }
 
 impl TryFrom<usize> for WmCommand {
@@ -14,6 +23,13 @@ impl TryFrom<usize> for WmCommand {
             1 => Ok(WmCommand::MenuToggleAutoStart),
             2 => Ok(WmCommand::MenuOpenConfigFile),
             3 => Ok(WmCommand::MenuQuit),
+            4 => Ok(WmCommand::MenuTogglePause),
+            v if (MENU_PAUSE_FOR_BASE..MENU_SELECT_PROFILE_BASE).contains(&v) => {
+                Ok(WmCommand::MenuPauseFor((v - MENU_PAUSE_FOR_BASE) as u64))
+            }
+            v if v >= MENU_SELECT_PROFILE_BASE => {
+                Ok(WmCommand::MenuSelectProfile(v - MENU_SELECT_PROFILE_BASE))
+            }
             _ => Err(value),
         }
     }
@@ -21,6 +37,13 @@ impl TryFrom<usize> for WmCommand {
 
 impl From<WmCommand> for usize {
     fn from(val: WmCommand) -> Self {
-        val as usize
+        match val {
+            WmCommand::MenuToggleAutoStart => 1,
+            WmCommand::MenuOpenConfigFile => 2,
+            WmCommand::MenuQuit => 3,
+            WmCommand::MenuTogglePause => 4,
+            WmCommand::MenuPauseFor(minutes) => MENU_PAUSE_FOR_BASE + minutes as usize,
+            WmCommand::MenuSelectProfile(index) => MENU_SELECT_PROFILE_BASE + index,
+        }
     }
 }